@@ -0,0 +1,27 @@
+//! Opening up large boards with a single reveal.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use minesweeper::{Game, MineSweeper};
+
+/// Reveals the middle of a `side` by `side` board sparse enough to clear in one go.
+/// The mines are placed beforehand so only the flood fill is measured.
+fn open_board(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flood");
+    group.sample_size(10);
+    for &side in &[100, 300, 1000] {
+        let mut ms = MineSweeper::new(side, side, side / 20, 9);
+        ms.generate(side / 2, side / 2);
+        group.bench_with_input(BenchmarkId::from_parameter(side), &ms, |b, ms| {
+            b.iter_batched(
+                || Game::new(ms.clone()),
+                |mut game| game.reveal(side / 2, side / 2),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, open_board);
+criterion_main!(benches);
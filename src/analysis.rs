@@ -0,0 +1,296 @@
+//! Measures of how much work a board takes to clear.
+//!
+//! 3BV is the fewest left clicks that clear a board without flags: one for each
+//! opening, and one for each number not on the edge of an opening. ZiNi also allows
+//! flags and chords, and is estimated greedily, chording wherever that saves the most.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::game::Game;
+use crate::minesweeper::{Content, Field, MineSweeper, Status};
+use crate::solver::Pos;
+use crate::topology::{Neighbours, Topology};
+
+/// How much work a board takes, from its layout alone
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Metrics {
+    /// Clicks to clear the board without flags
+    pub bbbv: usize,
+    /// Groups of touching squares with no mines next to them, each cleared by one click
+    pub openings: usize,
+    /// Groups of touching numbers not on the edge of any opening
+    pub islands: usize,
+    /// Clicks to clear the board using flags and chords, found greedily
+    pub zini: usize,
+}
+
+/// Measures `field`, which must have its mines placed
+pub fn metrics(field: &Field, topology: Topology) -> Metrics {
+    let openings = openings(field, topology);
+    Metrics {
+        bbbv: bbbv_with(field, &openings),
+        openings: openings.count,
+        islands: islands(field, &openings),
+        zini: zini_with(field, &openings),
+    }
+}
+
+/// Clicks needed to clear `field` without flags
+pub fn bbbv(field: &Field, topology: Topology) -> usize {
+    bbbv_with(field, &openings(field, topology))
+}
+
+/// How quickly and how economically a game was played
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score {
+    pub bbbv: usize,
+    /// Seconds played
+    pub time: f64,
+    /// Clicks made, wasted ones included
+    pub clicks: usize,
+}
+
+impl Score {
+    /// The score of a game so far
+    pub fn of(ms: &MineSweeper) -> Self {
+        Score { bbbv: bbbv(&ms.field, ms.topology), time: ms.time, clicks: ms.clicks }
+    }
+
+    /// 3BV cleared per second
+    pub fn bbbv_per_second(&self) -> f64 {
+        if self.time > 0.0 { self.bbbv as f64 / self.time } else { 0.0 }
+    }
+
+    /// The share of clicks that were needed, as 3BV over clicks made.
+    /// Can pass one when chording saves clicks.
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks > 0 { self.bbbv as f64 / self.clicks as f64 } else { 0.0 }
+    }
+}
+
+/// Parses an inclusive 3BV range, `MIN-MAX`
+pub fn parse_range(s: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("invalid 3BV range `{}`, expected MIN-MAX", s);
+    let (min, max) = s.split_once('-').ok_or_else(invalid)?;
+    let min = min.trim().parse().map_err(|_| invalid())?;
+    let max = max.trim().parse().map_err(|_| invalid())?;
+    if min > max {
+        return Err(format!("3BV range `{}` is empty", s));
+    }
+    Ok((min, max))
+}
+
+/// The opening each square with no mines around it belongs to
+struct Openings {
+    id: Vec<Vec<Option<usize>>>,
+    count: usize,
+    topology: Topology,
+}
+
+impl Openings {
+    fn neighbours(&self, row: usize, col: usize) -> Neighbours {
+        self.topology.neighbours(self.id.len(), self.id[0].len(), row, col)
+    }
+
+    /// Whether `(row, col)` is a number on the edge of an opening,
+    /// which is cleared along with it
+    fn borders(&self, field: &Field, row: usize, col: usize) -> bool {
+        field[row][col].content.number().is_some_and(|number| number > 0)
+            && self.neighbours(row, col).any(|(r, c)| self.id[r][c].is_some())
+    }
+}
+
+fn openings(field: &Field, topology: Topology) -> Openings {
+    let (rows, cols) = (field.len(), field[0].len());
+    let mut id = vec![vec![None; cols]; rows];
+    let mut count = 0;
+    for row in 0..rows {
+        for col in 0..cols {
+            if field[row][col].content != Content::Number(0) || id[row][col].is_some() {
+                continue;
+            }
+            id[row][col] = Some(count);
+            let mut queue = VecDeque::from(vec![(row, col)]);
+            while let Some((r, c)) = queue.pop_front() {
+                for (nr, nc) in topology.neighbours(rows, cols, r, c) {
+                    if field[nr][nc].content == Content::Number(0) && id[nr][nc].is_none() {
+                        id[nr][nc] = Some(count);
+                        queue.push_back((nr, nc));
+                    }
+                }
+            }
+            count += 1;
+        }
+    }
+    Openings { id, count, topology }
+}
+
+/// Numbers that have to be clicked one by one, since no opening clears them
+fn lone_numbers<'a>(field: &'a Field, openings: &'a Openings) -> impl Iterator<Item = Pos> + 'a {
+    let cols = field[0].len();
+    (0..field.len())
+        .flat_map(move |row| (0..cols).map(move |col| (row, col)))
+        .filter(move |&(row, col)| {
+            let content = &field[row][col].content;
+            content.number().is_some_and(|number| number > 0) && !openings.borders(field, row, col)
+        })
+}
+
+fn bbbv_with(field: &Field, openings: &Openings) -> usize {
+    openings.count + lone_numbers(field, openings).count()
+}
+
+fn islands(field: &Field, openings: &Openings) -> usize {
+    let lone: BTreeSet<Pos> = lone_numbers(field, openings).collect();
+    let mut seen = BTreeSet::new();
+    let mut count = 0;
+    for &start in &lone {
+        if !seen.insert(start) {
+            continue;
+        }
+        count += 1;
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some((r, c)) = queue.pop_front() {
+            for pos in openings.neighbours(r, c) {
+                if lone.contains(&pos) && seen.insert(pos) {
+                    queue.push_back(pos);
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Something that takes one click to clear on its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Unit {
+    Opening(usize),
+    Number(Pos),
+}
+
+/// Plays the board greedily: while chording some number clears more units than
+/// the clicks it costs to uncover it, flag around it and chord, take the best one.
+/// Whatever is left is clicked a unit at a time.
+fn zini_with(field: &Field, openings: &Openings) -> usize {
+    let (rows, cols) = (field.len(), field[0].len());
+    // a square is listed once for each mine it holds, so it is flagged that many times
+    let mines: Vec<Pos> = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .flat_map(|(row, col)| std::iter::repeat_n((row, col), field[row][col].content.mines() as usize))
+        .collect();
+    let mut ms = MineSweeper::new(rows, cols, 0, 0);
+    ms.mines = mines.len();
+    ms.per_square = field.iter().flatten().map(|square| square.content.mines()).max().unwrap_or(0).max(1);
+    ms.question_marks = false;
+    ms.topology = openings.topology;
+    ms.place_mines(&mines);
+    let mut game = Game::new(ms);
+
+    let unit = |(row, col): Pos| match openings.id[row][col] {
+        Some(id) => Some(Unit::Opening(id)),
+        None if field[row][col].content.is_mine() || openings.borders(field, row, col) => None,
+        None => Some(Unit::Number((row, col))),
+    };
+    let covered = |game: &Game, (row, col): Pos| game.minesweeper.get(row, col).status != Status::Uncovered;
+
+    loop {
+        let mut best: Option<(isize, Pos)> = None;
+        for row in 0..rows {
+            for col in 0..cols {
+                let content = &field[row][col].content;
+                if content.number().is_none_or(|number| number == 0) {
+                    continue;
+                }
+                let mut gained: BTreeSet<Unit> = openings.neighbours(row, col)
+                    .filter(|&pos| covered(&game, pos))
+                    .filter_map(unit)
+                    .collect();
+                let mut cost = 1;
+                if covered(&game, (row, col)) {
+                    gained.extend(unit((row, col)));
+                    cost += 1;
+                }
+                cost += openings.neighbours(row, col)
+                    .map(|(r, c)| (field[r][c].content.mines() - game.minesweeper.get(r, c).status.flags()) as usize)
+                    .sum::<usize>();
+                let premium = gained.len() as isize - cost as isize;
+                if best.is_none_or(|(most, _)| premium > most) {
+                    best = Some((premium, (row, col)));
+                }
+            }
+        }
+        let (row, col) = match best {
+            Some((premium, pos)) if premium > 0 => pos,
+            _ => break,
+        };
+        game.reveal(row, col);
+        for (r, c) in openings.neighbours(row, col) {
+            while game.minesweeper.get(r, c).status.flags() < field[r][c].content.mines() {
+                game.toggle_mark(r, c);
+            }
+        }
+        game.chord(row, col);
+    }
+
+    let left: BTreeSet<Unit> = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .filter(|&pos| covered(&game, pos))
+        .filter_map(unit)
+        .collect();
+    game.minesweeper.clicks + left.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::Difficulty;
+
+    /// A board with `mines` placed and nothing uncovered
+    fn board(rows: usize, cols: usize, mines: &[Pos]) -> Field {
+        let mut ms = MineSweeper::new(rows, cols, mines.len(), 0);
+        ms.place_mines(mines);
+        ms.field
+    }
+
+    #[test]
+    fn counts_openings_and_lone_numbers() {
+        // . . 1 * 1 . .    one opening on each side of the mine; every number borders one
+        let field = board(1, 7, &[(0, 3)]);
+        let measured = metrics(&field, Topology::Flat);
+        assert_eq!((measured.bbbv, measured.openings, measured.islands), (2, 2, 0));
+
+        // * 2 *    the 2 touches no opening, so it is an island of its own
+        let field = board(1, 3, &[(0, 0), (0, 2)]);
+        let measured = metrics(&field, Topology::Flat);
+        assert_eq!((measured.bbbv, measured.openings, measured.islands), (1, 0, 1));
+    }
+
+    #[test]
+    fn chording_beats_clicking_a_row_of_numbers() {
+        // 1 2 1 2 1
+        // * 2 * 2 *    numbers all around a row of mines: 3BV clicks each of them,
+        // 1 2 1 2 1    where ZiNi flags the mines and chords from the middle row
+        let field = board(3, 5, &[(1, 0), (1, 2), (1, 4)]);
+        let measured = metrics(&field, Topology::Flat);
+        assert_eq!(measured.bbbv, 12);
+        assert!(measured.zini < measured.bbbv);
+    }
+
+    #[test]
+    fn zini_never_exceeds_bbbv() {
+        for seed in 0..10 {
+            let mut ms = MineSweeper::with_difficulty(Difficulty::Expert, seed);
+            ms.generate(8, 15);
+            let measured = metrics(&ms.field, ms.topology);
+            assert!(measured.zini <= measured.bbbv);
+            assert!(measured.openings >= 1);
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("100-150"), Ok((100, 150)));
+        assert!(parse_range("150-100").is_err());
+        assert!(parse_range("150").is_err());
+    }
+}
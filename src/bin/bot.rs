@@ -0,0 +1,153 @@
+//! Plays many games with the bot and reports how it did, without opening a window.
+//!
+//! Game `i` of a run is generated from seed `--seed + i`, and the bot's guesses are
+//! drawn from the same seed, so a run gives the same results on any number of threads.
+
+use std::env;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use minesweeper::analysis;
+use minesweeper::bot::{self, Guess, Report};
+use minesweeper::{Difficulty, Game, Generation, MineSweeper, SafeStart, Topology};
+
+const USAGE: &str = "usage: minesweeper-bot [--games N] [--threads N] [--seed N] \
+                     [--difficulty beginner|intermediate|expert|ROWSxCOLSxMINES] \
+                     [--safe-start none|cell|opening] [--no-guess] [--3bv MIN-MAX] [--topology flat|torus|hex] [--guess safest|random]";
+
+/// Options given on the command line
+struct Options {
+    games: usize,
+    threads: usize,
+    seed: u64,
+    difficulty: Difficulty,
+    safe_start: SafeStart,
+    generation: Generation,
+    /// 3BV range generated boards must fall in
+    bbbv_range: Option<(usize, usize)>,
+    topology: Topology,
+    guess: Guess,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let mut options = Options {
+            games: 100,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: 0,
+            difficulty: Difficulty::default(),
+            safe_start: SafeStart::default(),
+            generation: Generation::default(),
+            bbbv_range: None,
+            topology: Topology::default(),
+            guess: Guess::default(),
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-n" | "--games" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.games = value.parse().map_err(|_| format!("invalid number of games `{}`", value))?;
+                },
+                "-j" | "--threads" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    let threads = value.parse().map_err(|_| format!("invalid number of threads `{}`", value))?;
+                    if threads == 0 {
+                        return Err("at least one thread is needed".to_string());
+                    }
+                    options.threads = threads;
+                },
+                "--seed" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.seed = value.parse().map_err(|_| format!("invalid seed `{}`", value))?;
+                },
+                "-d" | "--difficulty" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.difficulty = value.parse()?;
+                },
+                "--safe-start" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.safe_start = value.parse()?;
+                },
+                // no time limit, which would make the boards depend on how busy the machine is
+                "--no-guess" => options.generation = Generation::no_guess_attempts(Generation::NO_GUESS_ATTEMPTS),
+                "--3bv" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.bbbv_range = Some(analysis::parse_range(&value)?);
+                },
+                "--topology" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.topology = value.parse()?;
+                },
+                "--guess" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.guess = value.parse()?;
+                },
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn main() {
+    let options = Options::from_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(1);
+    });
+
+    let start = Instant::now();
+    let reports = run(&options);
+    let elapsed = start.elapsed();
+
+    let games = reports.len().max(1) as f64;
+    let wins = reports.iter().filter(|report| report.won).count();
+    let clicks: usize = reports.iter().map(|report| report.clicks).sum();
+    let guesses: usize = reports.iter().map(|report| report.guesses).sum();
+    let playing: Duration = reports.iter().map(|report| report.time).sum();
+    let bbbv: usize = reports.iter().map(|report| report.bbbv).sum();
+
+    println!("games:          {}", reports.len());
+    println!("won:            {} ({:.1}%)", wins, wins as f64 / games * 100.0);
+    println!("clicks/game:    {:.1}", clicks as f64 / games);
+    println!("clicks/s:       {:.1}", clicks as f64 / playing.as_secs_f64().max(f64::EPSILON));
+    println!("guesses/game:   {:.2}", guesses as f64 / games);
+    println!("3BV/game:       {:.1}", bbbv as f64 / games);
+    println!("3BV/s:          {:.1}", bbbv as f64 / playing.as_secs_f64().max(f64::EPSILON));
+    println!("efficiency:     {:.0}%", bbbv as f64 / clicks.max(1) as f64 * 100.0);
+    println!("time/game:      {:.2} ms", playing.as_secs_f64() * 1000.0 / games);
+    println!("total time:     {:.2} s on {} threads", elapsed.as_secs_f64(), options.threads);
+}
+
+/// Plays every game, sharing them out between the threads as each finishes one,
+/// and returns the reports in game order
+fn run(options: &Options) -> Vec<Report> {
+    let next = AtomicUsize::new(0);
+    let reports = Mutex::new(vec![None; options.games]);
+    thread::scope(|scope| {
+        for _ in 0..options.threads.min(options.games) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= options.games {
+                    break;
+                }
+                let seed = options.seed.wrapping_add(i as u64);
+                let mut ms = MineSweeper::with_difficulty(options.difficulty, seed);
+                ms.safe_start = options.safe_start;
+                ms.generation = options.generation;
+                ms.bbbv_range = options.bbbv_range;
+                ms.topology = options.topology;
+                let report = bot::play(&mut Game::new(ms), options.guess, seed);
+                reports.lock().unwrap()[i] = Some(report);
+            });
+        }
+    });
+    reports.into_inner().unwrap().into_iter().map(|report| report.expect("every game is played")).collect()
+}
@@ -0,0 +1,297 @@
+//! Terminal front end, for machines where the Piston window can't open.
+//!
+//! Move with the arrow keys or hjkl, reveal with space or enter (which chords on an
+//! uncovered number, like clicking it), flag with f, chord with c, start over with r
+//! and quit with q or escape.
+
+use std::env;
+use std::io::{self, Stdout, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, PrintStyledContent, StyledContent, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use minesweeper::minesweeper::parse_per_square;
+use minesweeper::{analysis, Content, Difficulty, Game, Generation, MineSweeper, Outcome, SafeStart, Square, Status, Topology};
+
+const USAGE: &str = "usage: minesweeper-tui [--difficulty beginner|intermediate|expert|ROWSxCOLSxMINES] \
+                     [--safe-start none|cell|opening] [--seed N] [--no-guess] [--3bv MIN-MAX] [--topology flat|torus|hex] [--multimines N] [--lives N] [--no-question-marks]";
+
+/// Options given on the command line
+struct Options {
+    difficulty: Difficulty,
+    safe_start: SafeStart,
+    seed: Option<u64>,
+    generation: Generation,
+    /// 3BV range generated boards must fall in
+    bbbv_range: Option<(usize, usize)>,
+    topology: Topology,
+    /// Most mines one square can hold
+    per_square: u8,
+    /// Mines that can be set off before the game is lost
+    lives: usize,
+    /// Whether marking cycles through a question mark after the flag
+    question_marks: bool,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let mut options = Options {
+            difficulty: Difficulty::default(),
+            safe_start: SafeStart::default(),
+            seed: None,
+            generation: Generation::default(),
+            bbbv_range: None,
+            topology: Topology::default(),
+            per_square: 1,
+            lives: 1,
+            question_marks: true,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-d" | "--difficulty" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.difficulty = value.parse()?;
+                },
+                "--safe-start" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.safe_start = value.parse()?;
+                },
+                "--seed" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    let seed = value.parse().map_err(|_| format!("invalid seed `{}`", value))?;
+                    options.seed = Some(seed);
+                },
+                // no time limit, so --seed and the first click always give the same board
+                "--no-guess" => options.generation = Generation::no_guess_attempts(Generation::NO_GUESS_ATTEMPTS),
+                "--3bv" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.bbbv_range = Some(analysis::parse_range(&value)?);
+                },
+                "--topology" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.topology = value.parse()?;
+                },
+                "--multimines" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.per_square = parse_per_square(&value)?;
+                },
+                "--lives" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.lives = value.parse().ok()
+                        .filter(|&lives| lives > 0)
+                        .ok_or_else(|| format!("invalid number of lives `{}`", value))?;
+                },
+                "--no-question-marks" => options.question_marks = false,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        if options.per_square > 1 && options.generation != Generation::Random {
+            return Err("--no-guess can't be used with --multimines, since the solver counts one mine to a square".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Puts the terminal in raw mode on an alternate screen, restoring it when dropped
+struct Screen {
+    out: Stdout,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(Screen { out })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() {
+    let options = Options::from_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(1);
+    });
+
+    let mut ms = MineSweeper::with_difficulty(options.difficulty, options.seed.unwrap_or_else(rand::random));
+    ms.safe_start = options.safe_start;
+    ms.generation = options.generation;
+    ms.bbbv_range = options.bbbv_range;
+    ms.topology = options.topology;
+    ms.per_square = options.per_square;
+    ms.lives = options.lives;
+    ms.question_marks = options.question_marks;
+    let mut game = Game::new(ms);
+
+    let result = Screen::enter().and_then(|mut screen| play(&mut game, &mut screen.out));
+    if let Err(err) = result {
+        eprintln!("terminal error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Runs the game until the player quits
+fn play(game: &mut Game, out: &mut Stdout) -> io::Result<()> {
+    let mut cursor = (0, 0);
+    let mut last_tick = Instant::now();
+    loop {
+        let now = Instant::now();
+        game.tick((now - last_tick).as_secs_f64());
+        last_tick = now;
+        draw(out, game, cursor)?;
+
+        // wake up regularly so the timer keeps counting
+        if !event::poll(Duration::from_millis(200))? { continue }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Resize(..) => {
+                queue!(out, Clear(ClearType::All))?;
+                continue;
+            },
+            _ => continue,
+        };
+
+        let (row, col) = cursor;
+        let ms = &game.minesweeper;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => cursor.0 = row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => cursor.0 = (row + 1).min(ms.rows - 1),
+            KeyCode::Left | KeyCode::Char('h') => cursor.1 = col.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => cursor.1 = (col + 1).min(ms.cols - 1),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if ms.get(row, col).status == Status::Uncovered {
+                    game.chord(row, col);
+                } else {
+                    game.reveal(row, col);
+                }
+            },
+            KeyCode::Char('f') => game.toggle_mark(row, col),
+            KeyCode::Char('c') => {
+                game.chord(row, col);
+            },
+            KeyCode::Char('u') => {
+                game.undo();
+            },
+            KeyCode::Char('y') => {
+                game.redo();
+            },
+            KeyCode::Char('r') => {
+                game.reset(rand::random());
+                queue!(out, Clear(ClearType::All))?;
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Draws the header, the board and a line of help
+fn draw(out: &mut Stdout, game: &Game, cursor: (usize, usize)) -> io::Result<()> {
+    let ms = &game.minesweeper;
+    let hex = ms.topology == Topology::Hex;
+    let width = ms.cols * 2 + hex as usize;
+
+    // the same readouts as the window: mines left, smiley and timer
+    let face = match game.state() {
+        Outcome::Playing => ":)",
+        Outcome::Won => "B)",
+        Outcome::Lost => "X(",
+    };
+    let mines = format!("{:03}", ms.mines_left().clamp(-99, 999));
+    let time = format!("{:03}", ms.seconds());
+    // in lives mode, the lives left follow the mine counter
+    let lives = if ms.lives > 1 { format!(" ♥{}", ms.lives.saturating_sub(ms.hits)) } else { String::new() };
+    let gap = width.saturating_sub(mines.len() + lives.chars().count() + face.len() + time.len()) / 2;
+    queue!(
+        out,
+        MoveTo(0, 0),
+        PrintStyledContent(mines.red().bold()),
+        PrintStyledContent(lives.red()),
+        Print(" ".repeat(gap)),
+        PrintStyledContent(face.yellow().bold()),
+        Print(" ".repeat(gap)),
+        PrintStyledContent(time.red().bold()),
+    )?;
+
+    for row in 0..ms.rows {
+        // the odd rows of a hex board sit half a cell to the right
+        let shift = (hex && row % 2 == 1) as u16;
+        queue!(out, MoveTo(shift, row as u16 + 2))?;
+        for col in 0..ms.cols {
+            let cell = cell(ms.get(row, col), game.state() == Outcome::Lost);
+            let cell = if (row, col) == cursor { cell.reverse() } else { cell };
+            queue!(out, PrintStyledContent(cell))?;
+        }
+    }
+
+    let mut footer = format!("seed {}", ms.seed);
+    if game.state() == Outcome::Won {
+        let score = analysis::Score::of(ms);
+        footer += &format!(
+            "  3BV {}  {:.2} 3BV/s  {:.0}% efficiency",
+            score.bbbv,
+            score.bbbv_per_second(),
+            score.efficiency() * 100.0,
+        );
+    }
+    queue!(
+        out,
+        MoveTo(0, ms.rows as u16 + 3),
+        Clear(ClearType::UntilNewLine),
+        Print(footer),
+        MoveTo(0, ms.rows as u16 + 4),
+        Print("arrows/hjkl move  space reveal  f flag  c chord  u undo  y redo  r reset  q quit"),
+    )?;
+    out.flush()
+}
+
+/// The two characters drawn for a square, showing every mine once the game is lost
+/// along with any set off before then.
+/// Where several flags or mines share a square, the second character counts them.
+fn cell(square: &Square, lost: bool) -> StyledContent<String> {
+    let mines = square.content.mines();
+    let count = |n: u8| if n > 1 { n.to_string() } else { " ".to_string() };
+    match square.status {
+        Status::Uncovered => match square.content {
+            Content::Mines(mines) => format!("*{}", count(mines)).black().on_red(),
+            Content::Number(0) => "  ".to_string().on_grey(),
+            Content::Number(n) => format!("{:<2}", n).with(number_colour(n)).bold().on_grey(),
+        },
+        Status::Flagged(_) if lost && mines == 0 => "X ".to_string().red().on_dark_grey(),
+        Status::Flagged(flags) => format!("F{}", count(flags)).red().bold().on_dark_grey(),
+        _ if lost && mines > 0 => format!("*{}", count(mines)).black().on_dark_grey(),
+        Status::Questioned => "? ".to_string().white().on_dark_grey(),
+        Status::Unmarked => "■ ".to_string().grey().on_dark_grey(),
+    }
+}
+
+/// The classic colour of each number
+fn number_colour(n: u8) -> Color {
+    match n {
+        1 => Color::Blue,
+        2 => Color::DarkGreen,
+        3 => Color::Red,
+        4 => Color::DarkBlue,
+        5 => Color::DarkRed,
+        6 => Color::DarkCyan,
+        7 => Color::Black,
+        _ => Color::DarkGrey,
+    }
+}
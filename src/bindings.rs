@@ -0,0 +1,204 @@
+//! Which keys and mouse buttons do what.
+//!
+//! Bindings are read from a JSON file mapping key and mouse button names to actions:
+//!
+//! ```json
+//! {
+//!     "keys": { "F": "Flag", "Return": "Reveal", "W": "CursorUp" },
+//!     "mouse": { "Left": "Reveal", "Right": "Flag" }
+//! }
+//! ```
+//!
+//! Each section given replaces the default bindings for it entirely,
+//! and a section left out keeps its defaults.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use piston::input::{Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+/// Why a bindings file couldn't be read
+#[derive(Debug)]
+pub enum BindingsError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file isn't valid JSON, or names a key, button or action that doesn't exist
+    Format(serde_json::Error),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingsError::Io(err) => write!(f, "could not read file: {}", err),
+            BindingsError::Format(err) => write!(f, "bindings are malformed: {}", err),
+        }
+    }
+}
+
+impl Error for BindingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BindingsError::Io(err) => Some(err),
+            BindingsError::Format(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for BindingsError {
+    fn from(err: io::Error) -> Self { BindingsError::Io(err) }
+}
+
+impl From<serde_json::Error> for BindingsError {
+    fn from(err: serde_json::Error) -> Self { BindingsError::Format(err) }
+}
+
+/// Something the player can bind a key or mouse button to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// Reveals a covered square, or chords on an uncovered one
+    Reveal,
+    /// Cycles the mark on a covered square
+    Flag,
+    Chord,
+    /// Starts over with a new board of the same size
+    Reset,
+    /// Stops the clock and hides the board until pressed again
+    Pause,
+    Save,
+    Load,
+    /// Takes back the last move, even the one that ended the game
+    Undo,
+    /// Makes the last move taken back again
+    Redo,
+    /// Points out a square that is certainly clear or certainly a mine
+    Hint,
+    /// Shows or hides the chance of a mine on each covered square
+    Heatmap,
+    /// Shows the best times and other statistics for the board's difficulty
+    BestTimes,
+    Beginner,
+    Intermediate,
+    Expert,
+    /// The difficulty given on the command line
+    Chosen,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+}
+
+/// Maps keys and mouse buttons to actions
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub keys: HashMap<Key, Action>,
+    pub mouse: HashMap<MouseButton, Action>,
+}
+
+impl Default for Bindings {
+    /// The original game's mouse buttons, plus keys for everything else.
+    /// The cursor moves with the arrow keys or hjkl, as in the terminal front end.
+    fn default() -> Self {
+        let keys = vec![
+            (Key::Return, Action::Reveal),
+            (Key::Space, Action::Flag),
+            (Key::C, Action::Chord),
+            (Key::R, Action::Reset),
+            (Key::P, Action::Pause),
+            (Key::F5, Action::Save),
+            (Key::F9, Action::Load),
+            (Key::U, Action::Undo),
+            (Key::Y, Action::Redo),
+            (Key::I, Action::Hint),
+            (Key::O, Action::Heatmap),
+            (Key::B, Action::BestTimes),
+            (Key::D1, Action::Beginner),
+            (Key::D2, Action::Intermediate),
+            (Key::D3, Action::Expert),
+            (Key::D4, Action::Chosen),
+            (Key::Up, Action::CursorUp),
+            (Key::Down, Action::CursorDown),
+            (Key::Left, Action::CursorLeft),
+            (Key::Right, Action::CursorRight),
+            (Key::K, Action::CursorUp),
+            (Key::J, Action::CursorDown),
+            (Key::H, Action::CursorLeft),
+            (Key::L, Action::CursorRight),
+        ];
+        let mouse = vec![
+            (MouseButton::Left, Action::Reveal),
+            (MouseButton::Right, Action::Flag),
+            (MouseButton::Middle, Action::Chord),
+        ];
+        Bindings {
+            keys: keys.into_iter().collect(),
+            mouse: mouse.into_iter().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Parses bindings in the file format
+    pub fn from_json(s: &str) -> Result<Self, BindingsError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Reads bindings from `path`
+    pub fn load(path: &Path) -> Result<Self, BindingsError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn key(&self, key: Key) -> Option<Action> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn mouse(&self, button: MouseButton) -> Option<Action> {
+        self.mouse.get(&button).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_given_replace_the_defaults() {
+        let bindings = Bindings::from_json(r#"{ "keys": { "F": "Flag", "W": "CursorUp" } }"#).unwrap();
+        assert_eq!(bindings.key(Key::F), Some(Action::Flag));
+        assert_eq!(bindings.key(Key::W), Some(Action::CursorUp));
+        assert_eq!(bindings.key(Key::Space), None);
+        assert_eq!(bindings.mouse, Bindings::default().mouse);
+    }
+
+    #[test]
+    fn round_trip_keeps_every_binding() {
+        let bindings = Bindings::default();
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(Bindings::from_json(&json).unwrap(), bindings);
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        for json in [
+            r#"{ "keys": { "NoSuchKey": "Flag" } }"#,
+            r#"{ "keys": { "F": "Fly" } }"#,
+            r#"{ "mouse": { "Left": "Fly" } }"#,
+        ] {
+            assert!(matches!(Bindings::from_json(json), Err(BindingsError::Format(_))));
+        }
+    }
+
+    #[test]
+    fn arrows_and_hjkl_both_move_the_cursor() {
+        let bindings = Bindings::default();
+        for (arrow, letter) in [(Key::Up, Key::K), (Key::Down, Key::J), (Key::Left, Key::H), (Key::Right, Key::L)] {
+            assert!(bindings.key(arrow).is_some());
+            assert_eq!(bindings.key(arrow), bindings.key(letter));
+        }
+    }
+}
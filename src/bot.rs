@@ -0,0 +1,134 @@
+//! A bot that plays games on its own, for comparing solving strategies.
+//!
+//! The bot reveals every square the solver proves clear and only guesses when
+//! nothing is proven. It never places flags, since the solver doesn't need them.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::analysis;
+use crate::game::{Game, Outcome};
+use crate::minesweeper::Status;
+use crate::solver::{self, Pos};
+
+/// How the bot picks a square when nothing is proven
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Guess {
+    /// The square least likely to hold a mine
+    #[default]
+    Safest,
+    /// Any covered square
+    Random,
+}
+
+impl FromStr for Guess {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "safest" => Ok(Guess::Safest),
+            "random" => Ok(Guess::Random),
+            _ => Err(format!("unknown guessing policy `{}`", s)),
+        }
+    }
+}
+
+/// How a game played by the bot went
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub won: bool,
+    /// Squares clicked, guesses included
+    pub clicks: usize,
+    /// Clicks on squares that weren't proven clear, the first one included
+    pub guesses: usize,
+    /// Time spent thinking and playing
+    pub time: Duration,
+    /// Clicks the board needs without flags, for comparing against `clicks`
+    pub bbbv: usize,
+}
+
+/// Plays `game` to the end, drawing any random guesses from `seed`
+pub fn play(game: &mut Game, guess: Guess, seed: u64) -> Report {
+    let start = Instant::now();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut clicks = 0;
+    let mut guesses = 0;
+    while game.state() == Outcome::Playing {
+        let ms = &game.minesweeper;
+        let safe = if ms.generated { solver::solve(&ms.field, ms.topology).safe } else { Default::default() };
+        if safe.is_empty() {
+            let (row, col) = pick(game, guess, &mut rng);
+            game.reveal(row, col);
+            guesses += 1;
+            clicks += 1;
+        } else {
+            for (row, col) in safe {
+                if game.minesweeper.get(row, col).status != Status::Uncovered {
+                    game.reveal(row, col);
+                    clicks += 1;
+                }
+            }
+        }
+    }
+    let time = start.elapsed();
+    let bbbv = if game.minesweeper.generated { analysis::bbbv(&game.minesweeper.field, game.minesweeper.topology) } else { 0 };
+    Report { won: game.state() == Outcome::Won, clicks, guesses, time, bbbv }
+}
+
+/// Chooses a covered square to guess, starting in the middle of a fresh board
+fn pick(game: &Game, guess: Guess, rng: &mut ChaCha8Rng) -> Pos {
+    let ms = &game.minesweeper;
+    if !ms.generated {
+        return (ms.rows / 2, ms.cols / 2);
+    }
+    let covered: Vec<Pos> = (0..ms.rows)
+        .flat_map(|row| (0..ms.cols).map(move |col| (row, col)))
+        .filter(|&(row, col)| ms.get(row, col).status != Status::Uncovered)
+        .collect();
+    if guess == Guess::Safest {
+        if let Some(odds) = solver::probabilities(&ms.field, ms.topology, ms.mines) {
+            let odds_of = |&(row, col): &Pos| odds[row][col].unwrap_or(1.0);
+            let lowest = covered.iter().map(odds_of).fold(1.0, f64::min);
+            // break ties at random, so the bot doesn't always favour the top left
+            let safest: Vec<Pos> = covered.iter().cloned().filter(|pos| odds_of(pos) <= lowest).collect();
+            return safest[rng.gen_range(0, safest.len())];
+        }
+    }
+    covered[rng.gen_range(0, covered.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{Difficulty, Generation, MineSweeper};
+
+    #[test]
+    fn no_guess_board_needs_one_guess() {
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Expert, 7);
+        ms.generation = Generation::no_guess_attempts(2000);
+        let report = play(&mut Game::new(ms), Guess::Safest, 0);
+        assert!(report.won);
+        assert_eq!(report.guesses, 1);
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_game() {
+        let run = || {
+            let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Intermediate, 12));
+            let report = play(&mut game, Guess::Random, 5);
+            (report.won, report.clicks, report.guesses, game.minesweeper.field)
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn safest_guesses_beat_random_ones() {
+        let wins = |guess| (0..20)
+            .filter(|&seed| play(&mut Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, seed)), guess, seed).won)
+            .count();
+        assert!(wins(Guess::Safest) >= wins(Guess::Random));
+    }
+}
@@ -0,0 +1,141 @@
+//! A board of the day, the same for everyone who plays it.
+//!
+//! The seed comes from the date, and the middle of the board is opened before
+//! anyone clicks, so the layout doesn't depend on where a player starts. Daily
+//! boards keep the default options for the same reason. Wins are kept in a
+//! leaderboard file in the same format as the statistics, which a team can share.
+
+use std::path::PathBuf;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::game::Game;
+use crate::minesweeper::{Difficulty, MineSweeper};
+use crate::stats::{Finished, Stats};
+use crate::topology::Topology;
+
+/// The seed of everyone's board on `date`, which reads as the date, like 20240131
+pub fn seed(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10_000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+/// Everyone's board at `difficulty` on `date`, with its middle open
+pub fn board(difficulty: Difficulty, date: NaiveDate) -> Game {
+    let mut game = Game::new(MineSweeper::with_difficulty(difficulty, seed(date)));
+    open(&mut game);
+    game
+}
+
+/// Whether `ms` is a board for `date`: generated from its seed, with every option
+/// that changes the board or how it is played left as it is by default
+pub fn is_board(ms: &MineSweeper, date: NaiveDate) -> bool {
+    let daily = MineSweeper::with_difficulty(Difficulty::of(ms.rows, ms.cols, ms.mines), seed(date));
+    (ms.seed, ms.safe_start, ms.generation, ms.bbbv_range, ms.topology, ms.per_square, ms.lives)
+        == (daily.seed, daily.safe_start, daily.generation, daily.bbbv_range, daily.topology, daily.per_square, daily.lives)
+}
+
+/// Reveals the middle of a daily board that was just set up or started over,
+/// which places the mines the same way for every player. The opening comes with
+/// the board rather than being a move: it isn't a click, can't be undone, and the
+/// clock waits for the player's own first move.
+pub fn open(game: &mut Game) {
+    let (row, col) = (game.minesweeper.rows / 2, game.minesweeper.cols / 2);
+    game.reveal(row, col);
+    game.minesweeper.started = false;
+    game.minesweeper.clicks = 0;
+    game.forget();
+}
+
+/// Where the leaderboard is kept, if the platform has a data directory
+pub fn leaderboard_path() -> Option<PathBuf> {
+    Stats::default_path().map(|path| path.with_file_name("daily.json"))
+}
+
+/// Each player's fastest clean win on the board for `date` at `difficulty`, fastest first
+pub fn ranking(leaderboard: &Stats, date: NaiveDate, difficulty: Difficulty) -> Vec<&Finished> {
+    let name = difficulty.to_string();
+    let mut best: Vec<&Finished> = vec![];
+    for game in &leaderboard.games {
+        let usual_board = game.topology == Topology::Flat && game.per_square == 1;
+        if game.date != date || game.difficulty != name || !usual_board || !game.real_win() {
+            continue;
+        }
+        match best.iter_mut().find(|other| other.name == game.name) {
+            Some(other) if game.time < other.time => *other = game,
+            Some(_) => {},
+            None => best.push(game),
+        }
+    }
+    best.sort_by(|a, b| a.time.total_cmp(&b.time));
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+
+    #[test]
+    fn everyone_gets_the_same_board_each_day() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(seed(date), 20240131);
+
+        let game = board(Difficulty::Expert, date);
+        assert_eq!(game.state(), Outcome::Playing);
+        assert_eq!(game.minesweeper.layout(), board(Difficulty::Expert, date).minesweeper.layout());
+        assert_ne!(game.minesweeper.layout(), board(Difficulty::Expert, date.succ_opt().unwrap()).minesweeper.layout());
+
+        // starting over comes back to the same board
+        let mut again = board(Difficulty::Expert, date);
+        again.reveal(0, 0);
+        again.reset(seed(date));
+        open(&mut again);
+        assert_eq!(again.minesweeper.field, game.minesweeper.field);
+        assert!(is_board(&again.minesweeper, date));
+
+        // the same seed with other options isn't the daily board
+        assert!(!is_board(&board(Difficulty::Expert, date.succ_opt().unwrap()).minesweeper, date));
+        let changes: [fn(&mut MineSweeper); 3] = [|ms| ms.lives = 3, |ms| ms.topology = Topology::Torus, |ms| ms.per_square = 2];
+        for change in changes {
+            let mut ms = again.minesweeper.fresh(seed(date));
+            change(&mut ms);
+            assert!(!is_board(&ms, date));
+        }
+    }
+
+    #[test]
+    fn opening_is_not_a_move() {
+        let mut game = board(Difficulty::Beginner, NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        game.tick(5.0);
+        assert_eq!((game.minesweeper.time, game.minesweeper.clicks), (0.0, 0));
+        assert!(!game.undo());
+
+        // the clock starts with the player's first move, even a chord
+        let (row, col) = (game.minesweeper.rows / 2, game.minesweeper.cols / 2);
+        game.chord(row, col);
+        game.tick(1.5);
+        assert_eq!((game.minesweeper.time, game.minesweeper.clicks), (1.5, 1));
+    }
+
+    #[test]
+    fn ranking_keeps_each_players_best_of_the_day() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Beginner, seed(date));
+        ms.won = true;
+        let mut leaderboard = Stats::default();
+        for &(name, time, day) in &[("ann", 30.0, date), ("bob", 20.0, date), ("ann", 25.0, date), ("bob", 5.0, date.pred_opt().unwrap())] {
+            ms.time = time;
+            leaderboard.record(Finished::new(&ms, name, day));
+        }
+        ms.undos = 1;
+        ms.time = 1.0;
+        leaderboard.record(Finished::new(&ms, "cat", date));
+        ms.undos = 0;
+        ms.topology = Topology::Torus;
+        leaderboard.record(Finished::new(&ms, "dan", date));
+
+        let ranked: Vec<_> = ranking(&leaderboard, date, Difficulty::Beginner).iter().map(|game| (game.name.as_str(), game.time)).collect();
+        assert_eq!(ranked, vec![("bob", 20.0), ("ann", 25.0)]);
+        assert!(ranking(&leaderboard, date, Difficulty::Expert).is_empty());
+    }
+}
@@ -0,0 +1,637 @@
+use std::collections::VecDeque;
+
+use crate::minesweeper::{Content, MineSweeper, Status};
+use crate::replay::{Action, Recorder};
+use crate::solver::Pos;
+
+/// Where a game stands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// What a move changed, so it can be taken back and made again
+#[derive(Clone, Debug, PartialEq)]
+enum Change {
+    /// Squares uncovered by a reveal or chord, each with the status it had before
+    Uncovered(Vec<(Pos, Status)>),
+    /// A square's mark going from one status to the next
+    Marked { pos: Pos, from: Status, to: Status },
+}
+
+/// The rules of the game, independent of any front end.
+/// Moves are ignored once the game is won or lost, apart from being undone.
+/// Uncovering a mine costs a life, and the game is lost once none are left.
+pub struct Game {
+    pub minesweeper: MineSweeper,
+    /// Records every move applied, when set
+    pub recorder: Option<Recorder>,
+    /// Moves made on this board, latest last
+    history: Vec<Change>,
+    /// Moves undone, latest undone last, until a new move is made
+    undone: Vec<Change>,
+}
+
+impl Game {
+    pub fn new(minesweeper: MineSweeper) -> Self {
+        Game { minesweeper, recorder: None, history: vec![], undone: vec![] }
+    }
+
+    /// Where the game stands
+    pub fn state(&self) -> Outcome {
+        if self.minesweeper.lost { Outcome::Lost }
+        else if self.minesweeper.won { Outcome::Won }
+        else { Outcome::Playing }
+    }
+
+    /// Reveals a covered square, opening up around it if it has no mines next to it,
+    /// and returns every square uncovered.
+    /// The first reveal places the mines and starts the clock.
+    pub fn reveal(&mut self, row: usize, col: usize) -> Vec<Pos> {
+        if self.state() != Outcome::Playing { return vec![] }
+        let status = &self.minesweeper.get(row, col).status;
+        if status != &Status::Unmarked && status != &Status::Questioned { return vec![] }
+
+        let mut layout = None;
+        if !self.minesweeper.generated {
+            self.minesweeper.generate(row, col);
+            layout = Some(self.minesweeper.layout());
+        }
+        self.record(Action::Reveal { row, col }, layout);
+        self.minesweeper.clicks += 1;
+        self.minesweeper.started = true;
+        let revealed = self.reveal_square(row, col);
+        self.check_won();
+        self.push(Change::Uncovered(revealed.clone()));
+        revealed.into_iter().map(|(pos, _)| pos).collect()
+    }
+
+    /// Cycles a covered square through flagged, questioned and unmarked,
+    /// skipping questioned when question marks are turned off. Where squares
+    /// can hold several mines, a square is flagged that many times over first.
+    pub fn toggle_mark(&mut self, row: usize, col: usize) {
+        if self.state() != Outcome::Playing { return }
+        if self.minesweeper.get(row, col).status == Status::Uncovered { return }
+        self.record(Action::Mark { row, col }, None);
+        self.minesweeper.clicks += 1;
+        let from = self.minesweeper.get(row, col).status.clone();
+        self.mark(row, col);
+        let to = self.minesweeper.get(row, col).status.clone();
+        if from != to {
+            self.push(Change::Marked { pos: (row, col), from, to });
+        }
+    }
+
+    /// Reveals every unflagged neighbour of an uncovered number once
+    /// as many flags as the number have been placed around it, counting
+    /// each flag on squares flagged several times,
+    /// and returns every square uncovered
+    pub fn chord(&mut self, row: usize, col: usize) -> Vec<Pos> {
+        if self.state() != Outcome::Playing { return vec![] }
+        if self.minesweeper.get(row, col).status != Status::Uncovered { return vec![] }
+        self.record(Action::Chord { row, col }, None);
+        self.minesweeper.clicks += 1;
+        // a board opened before play can be chorded on first
+        self.minesweeper.started = true;
+        let revealed = self.clear_around(row, col);
+        self.check_won();
+        if !revealed.is_empty() {
+            self.push(Change::Uncovered(revealed.clone()));
+        }
+        revealed.into_iter().map(|(pos, _)| pos).collect()
+    }
+
+    /// Takes back the last move, even the one that lost the game,
+    /// returning whether there was one
+    pub fn undo(&mut self) -> bool {
+        let change = match self.history.pop() {
+            Some(change) => change,
+            None => return false,
+        };
+        self.record(Action::Undo, None);
+        self.minesweeper.undos += 1;
+        match &change {
+            Change::Uncovered(squares) => {
+                for &((row, col), ref before) in squares {
+                    let square = self.minesweeper.get_mut(row, col);
+                    square.status = before.clone();
+                    match square.content.mines() as usize {
+                        0 => self.minesweeper.left += 1,
+                        mines => {
+                            self.minesweeper.flags -= mines;
+                            self.minesweeper.hits -= 1;
+                        },
+                    }
+                }
+                self.minesweeper.lost = false;
+                self.minesweeper.won = false;
+            },
+            Change::Marked { pos: (row, col), from, .. } => self.set_mark(*row, *col, from.clone()),
+        }
+        self.undone.push(change);
+        true
+    }
+
+    /// Makes the last move undone again, returning whether there was one
+    pub fn redo(&mut self) -> bool {
+        let change = match self.undone.pop() {
+            Some(change) => change,
+            None => return false,
+        };
+        self.record(Action::Redo, None);
+        match &change {
+            Change::Uncovered(squares) => {
+                for &((row, col), _) in squares {
+                    self.minesweeper.get_mut(row, col).status = Status::Uncovered;
+                    if self.minesweeper.get(row, col).content.is_mine() {
+                        self.explode(row, col);
+                    } else {
+                        self.minesweeper.left -= 1;
+                    }
+                }
+                self.check_won();
+            },
+            Change::Marked { pos: (row, col), to, .. } => self.set_mark(*row, *col, to.clone()),
+        }
+        self.history.push(change);
+        true
+    }
+
+    /// Advances the clock by `dt` seconds
+    pub fn tick(&mut self, dt: f64) {
+        self.minesweeper.tick(dt);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.tick(dt);
+        }
+    }
+
+    /// Starts over with a new layout of the same size and options
+    pub fn reset(&mut self, seed: u64) {
+        self.record(Action::Reset { seed }, None);
+        self.minesweeper = self.minesweeper.fresh(seed);
+        self.forget();
+    }
+
+    /// Switches to a different board, such as one of another difficulty or a loaded game
+    pub fn replace(&mut self, minesweeper: MineSweeper) {
+        self.record(Action::Replace { board: Box::new(minesweeper.clone()) }, None);
+        self.minesweeper = minesweeper;
+        self.forget();
+    }
+
+    /// Adds a move to the history; a new move can't be made alongside undone ones
+    fn push(&mut self, change: Change) {
+        self.history.push(change);
+        self.undone.clear();
+    }
+
+    /// Clears the history, for a new board
+    pub(crate) fn forget(&mut self) {
+        self.history.clear();
+        self.undone.clear();
+    }
+
+    fn record(&mut self, action: Action, layout: Option<Vec<Pos>>) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(action, layout);
+        }
+    }
+
+    fn check_won(&mut self) {
+        if self.minesweeper.left == 0 { self.minesweeper.won = true }
+    }
+
+    fn clear_around(&mut self, row: usize, col: usize) -> Vec<(Pos, Status)> {
+        let number = self.minesweeper.get(row, col).content.number().unwrap_or(0) as usize;
+        if number == 0 || number != self.flags_around(row, col) { return vec![] }
+
+        let mut revealed = vec![];
+        for (r, c) in self.minesweeper.neighbours(row, col) {
+            revealed.extend(self.reveal_square(r, c));
+        }
+        revealed
+    }
+
+    /// Uncovers a square, opening up around it while it has no mines next to it,
+    /// and returns each square uncovered with the status it had before.
+    /// Uses a queue rather than recursion, so a huge open board can't overflow the stack.
+    fn reveal_square(&mut self, row: usize, col: usize) -> Vec<(Pos, Status)> {
+        let mut revealed = vec![];
+        let mut queue = VecDeque::new();
+        if let Some(before) = self.uncover(row, col) {
+            queue.push_back(((row, col), before));
+        }
+        while let Some(((r, c), before)) = queue.pop_front() {
+            revealed.push(((r, c), before));
+            match self.minesweeper.get(r, c).content {
+                Content::Mines(_) => self.explode(r, c),
+                Content::Number(0) => {
+                    self.minesweeper.left -= 1;
+                    for (nr, nc) in self.minesweeper.neighbours(r, c) {
+                        if let Some(before) = self.uncover(nr, nc) {
+                            queue.push_back(((nr, nc), before));
+                        }
+                    }
+                },
+                _ => self.minesweeper.left -= 1,
+            }
+        }
+        revealed
+    }
+
+    /// Uncovers a single square unless it is flagged or already uncovered,
+    /// returning the status it had if it was
+    fn uncover(&mut self, row: usize, col: usize) -> Option<Status> {
+        let square = self.minesweeper.get_mut(row, col);
+        if matches!(square.status, Status::Flagged(_) | Status::Uncovered) { return None }
+        Some(std::mem::replace(&mut square.status, Status::Uncovered))
+    }
+
+    /// Flags around a square, mines already set off counting as flagged
+    fn flags_around(&self, row: usize, col: usize) -> usize {
+        self.minesweeper.neighbours(row, col)
+            .map(|(r, c)| {
+                let square = self.minesweeper.get(r, c);
+                match square.status {
+                    Status::Uncovered => square.content.mines() as usize,
+                    ref status => status.flags() as usize,
+                }
+            })
+            .sum()
+    }
+
+    fn mark(&mut self, row: usize, col: usize) {
+        let question_marks = self.minesweeper.question_marks;
+        let per_square = self.minesweeper.per_square;
+        let next = match self.minesweeper.get(row, col).status {
+            Status::Uncovered => Status::Uncovered,
+            Status::Unmarked => Status::Flagged(1),
+            Status::Flagged(flags) if flags < per_square => Status::Flagged(flags + 1),
+            Status::Flagged(_) if question_marks => Status::Questioned,
+            Status::Flagged(_) | Status::Questioned => Status::Unmarked,
+        };
+        self.set_mark(row, col, next);
+    }
+
+    /// Puts `status` on a covered square, keeping count of the flags
+    fn set_mark(&mut self, row: usize, col: usize, status: Status) {
+        let after = status.flags() as usize;
+        let before = std::mem::replace(&mut self.minesweeper.get_mut(row, col).status, status).flags() as usize;
+        self.minesweeper.flags = self.minesweeper.flags + after - before;
+    }
+
+    /// Sets off the mines on a square just uncovered, which count as flagged
+    /// from then on, losing the game if they took the last life
+    fn explode(&mut self, row: usize, col: usize) {
+        self.minesweeper.flags += self.minesweeper.get(row, col).content.mines() as usize;
+        self.minesweeper.hits += 1;
+        if self.minesweeper.hits >= self.minesweeper.lives {
+            self.minesweeper.lost = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{Difficulty, SafeStart};
+    use crate::topology::Topology;
+    use proptest::prelude::*;
+
+    /// A beginner game whose mines were placed by revealing the middle square
+    fn started_game() -> Game {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, 3));
+        game.minesweeper.safe_start = SafeStart::Opening;
+        game.reveal(4, 4);
+        game
+    }
+
+    fn squares_where(game: &Game, f: impl Fn(&Content) -> bool) -> Vec<(usize, usize)> {
+        let ms = &game.minesweeper;
+        (0..ms.rows)
+            .flat_map(|row| (0..ms.cols).map(move |col| (row, col)))
+            .filter(|&(row, col)| f(&ms.get(row, col).content))
+            .collect()
+    }
+
+    #[test]
+    fn first_reveal_generates_and_starts() {
+        let game = started_game();
+        assert!(game.minesweeper.generated);
+        assert!(game.minesweeper.started);
+        assert_eq!(game.minesweeper.get(4usize, 4usize).status, Status::Uncovered);
+        assert_eq!(game.state(), Outcome::Playing);
+    }
+
+    #[test]
+    fn revealing_a_mine_loses() {
+        let mut game = started_game();
+        let (row, col) = squares_where(&game, |content| content.is_mine())[0];
+        game.reveal(row, col);
+        assert_eq!(game.state(), Outcome::Lost);
+    }
+
+    #[test]
+    fn revealing_every_safe_square_wins() {
+        let mut game = started_game();
+        for (row, col) in squares_where(&game, |content| !content.is_mine()) {
+            game.reveal(row, col);
+        }
+        assert_eq!(game.state(), Outcome::Won);
+    }
+
+    #[test]
+    fn toggle_mark_cycles_and_counts_flags() {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, 3));
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Flagged(1));
+        assert_eq!(game.minesweeper.flags, 1);
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Questioned);
+        assert_eq!(game.minesweeper.flags, 0);
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Unmarked);
+    }
+
+    #[test]
+    fn toggle_mark_skips_question_marks_when_off() {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, 3));
+        game.minesweeper.question_marks = false;
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Flagged(1));
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Unmarked);
+        assert_eq!(game.minesweeper.flags, 0);
+    }
+
+    #[test]
+    fn flagged_squares_are_not_revealed() {
+        let mut game = started_game();
+        let (row, col) = squares_where(&game, |content| content.is_mine())[0];
+        game.toggle_mark(row, col);
+        game.reveal(row, col);
+        assert_eq!(game.state(), Outcome::Playing);
+    }
+
+    #[test]
+    fn chord_reveals_around_satisfied_number() {
+        let mut game = started_game();
+        let numbers = squares_where(&game, |content| !content.is_mine() && content != &Content::Number(0));
+        let &(row, col) = numbers.iter()
+            .find(|&&(row, col)| game.minesweeper.get(row, col).status == Status::Uncovered)
+            .expect("the opening is surrounded by numbers");
+        let around: Vec<_> = game.minesweeper.neighbours(row, col).collect();
+        for &(r, c) in &around {
+            if game.minesweeper.get(r, c).content.is_mine() {
+                game.toggle_mark(r, c);
+            }
+        }
+        game.chord(row, col);
+        assert_eq!(game.state(), Outcome::Playing);
+        for (r, c) in around {
+            let square = game.minesweeper.get(r, c);
+            let expected = if square.content.is_mine() { Status::Flagged(1) } else { Status::Uncovered };
+            assert_eq!(square.status, expected);
+        }
+    }
+
+    #[test]
+    fn stacked_mines_take_a_flag_each_before_chording() {
+        let mut ms = MineSweeper::new(3, 3, 3, 0);
+        ms.per_square = 2;
+        ms.question_marks = false;
+        // 2 1 .    two mines on the left square, one on the middle,
+        // 3 3 1    so the middle 3 chords only once the left is flagged twice
+        // 0 0 0
+        ms.place_mines(&[(0, 0), (0, 0), (0, 1)]);
+        let mut game = Game::new(ms);
+        game.reveal(2, 2);
+        assert_eq!(game.minesweeper.left, 1);
+
+        game.toggle_mark(0, 0);
+        game.toggle_mark(0, 1);
+        assert!(game.chord(1, 1).is_empty());
+        game.toggle_mark(0, 0);
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Flagged(2));
+        assert_eq!(game.minesweeper.mines_left(), 0);
+        assert_eq!(game.chord(1, 1), vec![(0, 2)]);
+        assert_eq!(game.state(), Outcome::Won);
+    }
+
+    #[test]
+    fn lives_are_lost_one_mine_at_a_time() {
+        let mut ms = MineSweeper::new(3, 3, 2, 0);
+        ms.lives = 2;
+        // * 1 0    setting off the top mine costs a life, and it counts
+        // 2 2 0    as flagged, so the middle 2 chords once the other is
+        // * 1 0
+        ms.place_mines(&[(0, 0), (2, 0)]);
+        let mut game = Game::new(ms);
+        game.reveal(0, 0);
+        assert_eq!(game.state(), Outcome::Playing);
+        assert_eq!((game.minesweeper.hits, game.minesweeper.mines_left()), (1, 1));
+        assert_eq!(crate::save::validate(&game.minesweeper), Ok(()));
+        game.reveal(1, 1);
+        assert!(game.chord(1, 1).is_empty());
+        game.toggle_mark(2, 0);
+        game.chord(1, 1);
+        assert_eq!(game.state(), Outcome::Won);
+
+        for _ in 0..4 {
+            assert!(game.undo());
+        }
+        assert_eq!(game.minesweeper.get(0usize, 0usize).status, Status::Unmarked);
+        assert_eq!((game.minesweeper.hits, game.minesweeper.mines_left()), (0, 2));
+        assert!(game.redo());
+        game.reveal(2, 0);
+        assert_eq!(game.state(), Outcome::Lost);
+        assert_eq!(game.minesweeper.hits, 2);
+    }
+
+    #[test]
+    fn undo_takes_back_the_losing_click() {
+        let mut game = started_game();
+        let (row, col) = squares_where(&game, |content| content.is_mine())[0];
+        let before = game.minesweeper.field.clone();
+        game.reveal(row, col);
+        assert!(game.undo());
+        assert_eq!(game.state(), Outcome::Playing);
+        assert_eq!(game.minesweeper.field, before);
+        assert_eq!(game.minesweeper.undos, 1);
+
+        assert!(game.redo());
+        assert_eq!(game.state(), Outcome::Lost);
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn new_move_drops_undone_ones() {
+        let mut game = started_game();
+        game.toggle_mark(0, 0);
+        game.undo();
+        game.toggle_mark(8, 8);
+        assert!(!game.redo());
+        game.reset(1);
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn hex_boards_count_open_and_win_on_six_neighbours() {
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Intermediate, 5);
+        ms.topology = Topology::Hex;
+        ms.safe_start = SafeStart::Opening;
+        let mut game = Game::new(ms);
+        let opened = game.reveal(8, 8);
+        assert!(opened.len() > 1);
+
+        let ms = &game.minesweeper;
+        for (row, col) in squares_where(&game, |content| !content.is_mine()) {
+            let around = ms.neighbours(row, col).filter(|&(r, c)| ms.get(r, c).content.is_mine()).count() as u8;
+            assert_eq!(ms.get(row, col).content, Content::Number(around));
+            // an opening only spreads to the six cells touching each zero
+            if ms.get(row, col).status == Status::Uncovered && ms.get(row, col).content == Content::Number(0) {
+                assert!(ms.neighbours(row, col).all(|(r, c)| ms.get(r, c).status == Status::Uncovered));
+            }
+        }
+        assert_eq!(game.minesweeper.left, covered_safe_squares(&game));
+
+        let mines = squares_where(&game, |content| content.is_mine());
+        for &(row, col) in &mines {
+            game.toggle_mark(row, col);
+        }
+        // with every mine flagged, chording the numbers clears whatever the opening touches,
+        // and revealing reaches any cells walled off by mines
+        let mut chorded = 0;
+        for (row, col) in squares_where(&game, |content| !content.is_mine()) {
+            if game.minesweeper.get(row, col).status != Status::Uncovered {
+                game.reveal(row, col);
+            }
+            chorded += game.chord(row, col).len();
+        }
+        assert!(chorded > 0);
+        assert_eq!(game.state(), Outcome::Won);
+    }
+
+    /// Squares without a mine that are still covered, which `left` should always count
+    fn covered_safe_squares(game: &Game) -> usize {
+        game.minesweeper.field.iter().flatten()
+            .filter(|square| !square.content.is_mine() && square.status != Status::Uncovered)
+            .count()
+    }
+
+    #[test]
+    fn revealing_a_mine_leaves_left_alone() {
+        let mut game = started_game();
+        let left = game.minesweeper.left;
+        let (row, col) = squares_where(&game, |content| content.is_mine())[0];
+        assert_eq!(game.reveal(row, col), vec![(row, col)]);
+        assert_eq!(game.minesweeper.left, left);
+    }
+
+    #[test]
+    fn reveal_returns_newly_uncovered_squares() {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, 3));
+        let revealed = game.reveal(4, 4);
+        let uncovered = squares_where(&game, |_| true).into_iter()
+            .filter(|&(row, col)| game.minesweeper.get(row, col).status == Status::Uncovered)
+            .count();
+        assert_eq!(revealed.len(), uncovered);
+        assert_eq!(game.minesweeper.left, covered_safe_squares(&game));
+        assert!(game.reveal(4, 4).is_empty());
+    }
+
+    #[test]
+    fn flood_fills_huge_open_board() {
+        // so few mines that none are boxed in, and one reveal clears the board
+        let mut game = Game::new(MineSweeper::new(1000, 1000, 50, 9));
+        let revealed = game.reveal(500, 500);
+        assert_eq!(revealed.len(), 1000 * 1000 - 50);
+        assert_eq!(game.minesweeper.left, 0);
+        assert_eq!(game.state(), Outcome::Won);
+    }
+
+    /// A move at some square, with indices wrapped onto the board
+    #[derive(Clone, Debug)]
+    enum Move {
+        Reveal(usize, usize),
+        Mark(usize, usize),
+        Chord(usize, usize),
+    }
+
+    fn moves() -> impl Strategy<Value = Vec<Move>> {
+        let square = (0..100usize, 0..100usize);
+        let action = prop_oneof![
+            square.clone().prop_map(|(row, col)| Move::Reveal(row, col)),
+            square.clone().prop_map(|(row, col)| Move::Mark(row, col)),
+            square.prop_map(|(row, col)| Move::Chord(row, col)),
+        ];
+        proptest::collection::vec(action, 1..60)
+    }
+
+    proptest! {
+        #[test]
+        fn left_counts_covered_safe_squares(
+            rows in 1..20usize,
+            cols in 1..20usize,
+            density in 0.0..0.5f64,
+            seed: u64,
+            moves in moves(),
+        ) {
+            let mines = ((rows * cols) as f64 * density) as usize;
+            let mut game = Game::new(MineSweeper::new(rows, cols, mines, seed));
+            for action in moves {
+                match action {
+                    Move::Reveal(row, col) => {
+                        game.reveal(row % rows, col % cols);
+                    },
+                    Move::Mark(row, col) => game.toggle_mark(row % rows, col % cols),
+                    Move::Chord(row, col) => {
+                        game.chord(row % rows, col % cols);
+                    },
+                }
+                // before the mines are placed every square counts as safe
+                if game.minesweeper.generated {
+                    prop_assert_eq!(game.minesweeper.left, covered_safe_squares(&game));
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn undo_and_redo_retrace_every_move(seed: u64, moves in moves()) {
+            let mut game = Game::new(MineSweeper::new(9, 9, 10, seed));
+            // undo leaves the mines in place, so only what the player sees is compared
+            let snapshot = |game: &Game| {
+                let ms = &game.minesweeper;
+                let statuses: Vec<Status> = ms.field.iter().flatten().map(|square| square.status.clone()).collect();
+                (statuses, ms.left, ms.flags, game.state())
+            };
+            let mut before_each = vec![];
+            for action in moves {
+                let before = snapshot(&game);
+                match action {
+                    Move::Reveal(row, col) => {
+                        game.reveal(row % 9, col % 9);
+                    },
+                    Move::Mark(row, col) => game.toggle_mark(row % 9, col % 9),
+                    Move::Chord(row, col) => {
+                        game.chord(row % 9, col % 9);
+                    },
+                }
+                if snapshot(&game) != before {
+                    before_each.push(before);
+                }
+            }
+            let last = snapshot(&game);
+            while game.undo() {
+                prop_assert_eq!(Some(snapshot(&game)), before_each.pop());
+            }
+            prop_assert!(before_each.is_empty());
+            while game.redo() {}
+            prop_assert_eq!(snapshot(&game), last);
+        }
+    }
+}
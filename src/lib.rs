@@ -0,0 +1,19 @@
+//! The game engine: board generation, the rules of play, saves, replays, statistics,
+//! daily boards and time attacks, a deduction solver and a bot.
+//! Has no graphics dependencies, so bots, tests and other front ends can link it directly.
+
+pub mod analysis;
+pub mod bot;
+pub mod daily;
+pub mod game;
+pub mod minesweeper;
+pub mod replay;
+pub mod save;
+pub mod solver;
+pub mod stats;
+pub mod time_attack;
+pub mod topology;
+
+pub use crate::game::{Game, Outcome};
+pub use crate::minesweeper::{Content, Difficulty, Field, Generation, MineSweeper, SafeStart, Square, Status};
+pub use crate::topology::Topology;
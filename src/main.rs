@@ -1,67 +1,440 @@
 // TODO 
 // - MAKE AN ALERT FOR JEFFERY BECAUSE YOU LOVE HIM
-// - BUG - when bomb is on far right side, the space directly to the left often doesn't get
-// calculated properly
-// - add smiley
-//   - requires facial animations while clicking on flagged
-// - add numbers
-//   - need to create time and mines variables that are shared
-//   - these to have to have numeric representations in the code
-//     like how the open spaces have representations in the code.
-//     OR not and just store it as a number
-//   - TODO how tf to timers work in this language, std::Duration?
-// - levels of difficulty
-//   - look into the source code to see what the difficulties are
-//     search for "Expert"
-// - remove now-redudant parts of setting size passing
-
-use piston::window::WindowSettings;
-use piston::input::{RenderEvent};
+
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use piston::window::{AdvancedWindow, WindowSettings};
+use piston::input::{Button, GenericEvent, Key, RenderEvent};
 use piston::event_loop::{Events, EventSettings};
+use chrono::Local;
 use glutin_window::GlutinWindow;
 use opengl_graphics::{OpenGL, GlGraphics};
 
-mod minesweeper;
+use minesweeper::{analysis, daily, save, Difficulty, Game, Generation, MineSweeper, Outcome, SafeStart, Topology};
+use minesweeper::minesweeper::parse_per_square;
+use minesweeper::replay::{Player, Recorder, Replay};
+use minesweeper::stats::Stats;
+use minesweeper::time_attack::TimeAttack;
+
+mod bindings;
 mod minesweeper_controller;
 mod minesweeper_view;
+mod text;
 
-pub use crate::minesweeper::{MineSweeper, ROWS, COLS};
+pub use crate::bindings::{Action, Bindings};
 pub use crate::minesweeper_controller::MineSweeperController;
 pub use crate::minesweeper_view::{MineSweeperView, MineSweeperViewSettings};
 
+const USAGE: &str = "usage: minesweeper [--difficulty beginner|intermediate|expert|ROWSxCOLSxMINES] \
+                     [--safe-start none|cell|opening] [--seed N] [--no-guess] [--3bv MIN-MAX] [--topology flat|torus|hex] [--multimines N] [--lives N] [--no-question-marks] \
+                     [--load FILE] [--record FILE | --replay FILE] [--bindings FILE] \
+                     [--name NAME] [--stats FILE] [--export-stats FILE] \
+                     [--daily [--leaderboard FILE] | --time-attack MINUTES]";
+
+/// Where F5 saves and F9 loads when no file was given to `--load`
+const DEFAULT_SAVE_FILE: &str = "minesweeper-save.json";
+
+/// Key and mouse bindings read at startup, when present and no file was given to `--bindings`
+const DEFAULT_BINDINGS_FILE: &str = "minesweeper-bindings.json";
+
+/// Options given on the command line
+struct Options {
+    difficulty: Difficulty,
+    safe_start: SafeStart,
+    seed: Option<u64>,
+    generation: Generation,
+    /// 3BV range generated boards must fall in
+    bbbv_range: Option<(usize, usize)>,
+    topology: Topology,
+    /// Most mines one square can hold
+    per_square: u8,
+    /// Mines that can be set off before the game is lost
+    lives: usize,
+    /// Whether marking cycles through a question mark after the flag
+    question_marks: bool,
+    /// Game to resume, if any
+    load: Option<PathBuf>,
+    /// Where to write a replay of this session
+    record: Option<PathBuf>,
+    /// Replay to play back instead of playing
+    replay: Option<PathBuf>,
+    /// Key and mouse bindings to use instead of the defaults
+    bindings: Option<PathBuf>,
+    /// The name best times are recorded under
+    name: String,
+    /// Where statistics are kept, instead of the data directory
+    stats: Option<PathBuf>,
+    /// Where to write the statistics as CSV, instead of playing
+    export_stats: Option<PathBuf>,
+    /// Whether to play today's board, the same for everyone
+    daily: bool,
+    /// Where daily wins are kept, instead of the data directory
+    leaderboard: Option<PathBuf>,
+    /// Seconds to clear as many beginner boards as possible in, if playing a time attack
+    time_attack: Option<f64>,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let mut options = Options {
+            difficulty: Difficulty::default(),
+            safe_start: SafeStart::default(),
+            seed: None,
+            generation: Generation::default(),
+            bbbv_range: None,
+            topology: Topology::default(),
+            per_square: 1,
+            lives: 1,
+            question_marks: true,
+            load: None,
+            record: None,
+            replay: None,
+            bindings: None,
+            name: env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_else(|_| "anonymous".to_string()),
+            stats: None,
+            export_stats: None,
+            daily: false,
+            leaderboard: None,
+            time_attack: None,
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-d" | "--difficulty" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.difficulty = value.parse()?;
+                },
+                "--safe-start" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.safe_start = value.parse()?;
+                },
+                "--seed" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    let seed = value.parse().map_err(|_| format!("invalid seed `{}`", value))?;
+                    options.seed = Some(seed);
+                },
+                // no time limit, so --seed and the first click always give the same board
+                "--no-guess" => options.generation = Generation::no_guess_attempts(Generation::NO_GUESS_ATTEMPTS),
+                "--3bv" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.bbbv_range = Some(analysis::parse_range(&value)?);
+                },
+                "--topology" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.topology = value.parse()?;
+                },
+                "--multimines" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.per_square = parse_per_square(&value)?;
+                },
+                "--lives" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.lives = value.parse().ok()
+                        .filter(|&lives| lives > 0)
+                        .ok_or_else(|| format!("invalid number of lives `{}`", value))?;
+                },
+                "--no-question-marks" => options.question_marks = false,
+                "--load" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.load = Some(PathBuf::from(value));
+                },
+                "--record" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.record = Some(PathBuf::from(value));
+                },
+                "--replay" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.replay = Some(PathBuf::from(value));
+                },
+                "--bindings" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.bindings = Some(PathBuf::from(value));
+                },
+                "--name" => {
+                    options.name = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                },
+                "--stats" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.stats = Some(PathBuf::from(value));
+                },
+                "--export-stats" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.export_stats = Some(PathBuf::from(value));
+                },
+                "--daily" => options.daily = true,
+                "--leaderboard" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    options.leaderboard = Some(PathBuf::from(value));
+                },
+                "--time-attack" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    let minutes: f64 = value.parse().ok()
+                        .filter(|&minutes: &f64| minutes > 0.0 && minutes.is_finite())
+                        .ok_or_else(|| format!("invalid number of minutes `{}`", value))?;
+                    options.time_attack = Some(minutes * 60.0);
+                },
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        if options.record.is_some() && options.replay.is_some() {
+            return Err("--record and --replay can't be used together".to_string());
+        }
+        if options.per_square > 1 && options.generation != Generation::Random {
+            return Err("--no-guess can't be used with --multimines, since the solver counts one mine to a square".to_string());
+        }
+        if (options.daily || options.time_attack.is_some()) && (options.load.is_some() || options.replay.is_some()) {
+            return Err("--daily and --time-attack play new boards, so can't be used with --load or --replay".to_string());
+        }
+        if options.time_attack.is_some() && (options.topology != Topology::default() || options.per_square > 1 || options.lives > 1) {
+            return Err("--time-attack plays beginner boards, so can't be used with --topology, --multimines or --lives".to_string());
+        }
+        if options.daily {
+            if options.time_attack.is_some() {
+                return Err("--daily and --time-attack can't be used together".to_string());
+            }
+            // everyone has to get the same board
+            if options.seed.is_some() || options.safe_start != SafeStart::default() || options.generation != Generation::default()
+                || options.bbbv_range.is_some() || options.topology != Topology::default() || options.per_square > 1 || options.lives > 1 {
+                return Err("--daily plays the same board as everyone else, so its options can't be changed".to_string());
+            }
+        } else if options.leaderboard.is_some() {
+            return Err("--leaderboard only applies to --daily".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// The difficulty a difficulty shortcut switches to,
+/// `chosen` being whatever was given on the command line
+fn difficulty_for(action: Action, chosen: Difficulty) -> Option<Difficulty> {
+    match action {
+        Action::Beginner => Some(Difficulty::Beginner),
+        Action::Intermediate => Some(Difficulty::Intermediate),
+        Action::Expert => Some(Difficulty::Expert),
+        Action::Chosen => Some(chosen),
+        _ => None,
+    }
+}
+
+/// Window title showing the seed, so a board can be shared or reported,
+/// along with how well a won game was played, or the playback state when watching a replay.
+/// The daily board shows its date instead, and a time attack its score.
+fn title(controller: &MineSweeperController, player: Option<&Player>) -> String {
+    let game = &controller.game;
+    if let Some(time_attack) = &controller.time_attack {
+        let state = if time_attack.over() { " - time's up" } else { "" };
+        return format!("Mine Sweeper - time attack - {} cleared, {} lost{}", time_attack.cleared, time_attack.lost, state);
+    }
+    if let (Some(date), None) = (controller.daily, player) {
+        let state = match game.state() {
+            Outcome::Won => format!(" - won in {:.2}s", game.minesweeper.time),
+            Outcome::Lost => " - lost".to_string(),
+            Outcome::Playing => String::new(),
+        };
+        return format!("Mine Sweeper - daily {}{}", date, state);
+    }
+    match player {
+        Some(player) => format!(
+            "Mine Sweeper - replay x{}{}",
+            player.speed,
+            if player.paused { " (paused)" } else if player.finished() { " (finished)" } else { "" },
+        ),
+        None if game.state() == Outcome::Won => {
+            let score = analysis::Score::of(&game.minesweeper);
+            format!(
+                "Mine Sweeper - seed {} - won, 3BV {}, {:.2} 3BV/s, {:.0}% efficiency",
+                game.minesweeper.seed,
+                score.bbbv,
+                score.bbbv_per_second(),
+                score.efficiency() * 100.0,
+            )
+        },
+        None => format!("Mine Sweeper - seed {}", game.minesweeper.seed),
+    }
+}
+
+/// Builds a fresh board for `difficulty` with the remaining command line options applied
+fn new_board(difficulty: Difficulty, seed: u64, options: &Options) -> MineSweeper {
+    let mut ms = MineSweeper::with_difficulty(difficulty, seed);
+    ms.safe_start = options.safe_start;
+    ms.generation = options.generation;
+    ms.bbbv_range = options.bbbv_range;
+    ms.topology = options.topology;
+    ms.per_square = options.per_square;
+    ms.lives = options.lives;
+    ms.question_marks = options.question_marks;
+    ms
+}
+
+/// Fastest and slowest replay speeds
+const MAX_SPEED: f64 = 16.0;
+const MIN_SPEED: f64 = 0.25;
+
+/// Drives replay playback: space pauses, right arrow steps one move,
+/// up and down arrows change speed and backspace starts over
+fn replay_event<E: GenericEvent>(player: &mut Player, game: &mut Game, e: &E) {
+    if let Some(args) = e.update_args() {
+        player.advance(game, args.dt);
+    }
+    if let Some(Button::Keyboard(key)) = e.press_args() {
+        match key {
+            Key::Space => player.paused = !player.paused,
+            Key::Right => {
+                player.paused = true;
+                player.step(game);
+            },
+            Key::Up => player.speed = (player.speed * 2.0).min(MAX_SPEED),
+            Key::Down => player.speed = (player.speed / 2.0).max(MIN_SPEED),
+            Key::Backspace => player.restart(game),
+            _ => {},
+        }
+    }
+}
+
+fn exit_with(path: &Path, err: impl fmt::Display) -> ! {
+    eprintln!("{}: {}", path.display(), err);
+    process::exit(1);
+}
+
 fn main() {
+    let options = Options::from_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(1);
+    });
+    let difficulty = options.difficulty;
+
+    let stats_file = options.stats.clone().or_else(Stats::default_path);
+    let stats = match &stats_file {
+        Some(path) => Stats::load(path).unwrap_or_else(|err| exit_with(path, err)),
+        None => Stats::default(),
+    };
+    if let Some(path) = &options.export_stats {
+        stats.export(path).unwrap_or_else(|err| exit_with(path, err));
+        return;
+    }
+
+    let save_file = options.load.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_FILE));
+
     // initialize custom classes to handle events and the like
     // model
-    let  ms = MineSweeper::new();
+    let mut player = options.replay.as_ref()
+        .map(|path| Player::new(Replay::load(path).unwrap_or_else(|err| exit_with(path, err))));
+    let today = Local::now().date_naive();
+    let mut game = match (&player, &options.load) {
+        (Some(player), _) => player.start(),
+        (None, Some(path)) => Game::new(save::load(path).unwrap_or_else(|err| exit_with(path, err))),
+        (None, None) if options.daily => daily::board(difficulty, today),
+        (None, None) if options.time_attack.is_some() => {
+            Game::new(new_board(Difficulty::Beginner, options.seed.unwrap_or_else(rand::random), &options))
+        },
+        (None, None) => Game::new(new_board(difficulty, options.seed.unwrap_or_else(rand::random), &options)),
+    };
+    if options.record.is_some() {
+        game.recorder = Some(Recorder::new(&game.minesweeper));
+    }
+    let bindings = match &options.bindings {
+        Some(path) => Bindings::load(path).unwrap_or_else(|err| exit_with(path, err)),
+        None if Path::new(DEFAULT_BINDINGS_FILE).exists() => {
+            let path = Path::new(DEFAULT_BINDINGS_FILE);
+            Bindings::load(path).unwrap_or_else(|err| exit_with(path, err))
+        },
+        None => Bindings::default(),
+    };
     // controller
-    let mut ms_c = MineSweeperController::new(ms);
+    let mut ms_c = MineSweeperController::new(game, bindings);
+    ms_c.stats = stats;
+    ms_c.stats_file = stats_file;
+    ms_c.name = options.name.clone();
+    if options.daily {
+        let leaderboard_file = options.leaderboard.clone().or_else(daily::leaderboard_path);
+        if let Some(path) = &leaderboard_file {
+            ms_c.leaderboard = Stats::load(path).unwrap_or_else(|err| exit_with(path, err));
+        }
+        ms_c.daily = Some(today);
+        ms_c.leaderboard_file = leaderboard_file;
+    }
+    ms_c.time_attack = options.time_attack.map(TimeAttack::new);
     // view
-    let settings = MineSweeperViewSettings::new(ROWS, COLS, 2.5);
-
-    let width: f64 = settings.cols as f64 * settings.square_side + 2.0 * settings.border_long;
-    let height: f64 = settings.rows as f64 * settings.square_side + 3.0 * settings.border_long + settings.smiley_side;
+    let ms = &ms_c.game.minesweeper;
+    let mut settings = MineSweeperViewSettings::new(ms.rows, ms.cols, ms.topology, 2.5);
 
     // create window
     let opengl = OpenGL::V3_2;
-    let window_settings = WindowSettings::new("Mine Sweeper", [width, height])
+    let mut current_title = title(&ms_c, player.as_ref());
+    let window_settings = WindowSettings::new(current_title.clone(), [settings.scr_width, settings.scr_height])
         .graphics_api(opengl)
         .resizable(false)
         .exit_on_esc(true);
     let mut window: GlutinWindow = window_settings.build()
         .expect("Could not create window");
 
+    // updates drive the game clock, so the loop can't be lazy
     let mut event_settings = EventSettings::new();
-    event_settings.lazy = true;
+    event_settings.ups = 10;
+    event_settings.max_fps = 30;
     let mut events = Events::new(event_settings);
     let mut gl = GlGraphics::new(opengl);
 
-    let ms_v = MineSweeperView::new(settings.clone());
+    let mut ms_v = MineSweeperView::new(settings);
     
     // event loop
     while let Some(e) = events.next(&mut window) {
 
-        // handle input event
-        ms_c.event(settings.clone(), &e);
+        // handle input event, unless a replay is doing the playing
+        let action = match &mut player {
+            Some(player) => {
+                replay_event(player, &mut ms_c.game, &e);
+                None
+            },
+            None => ms_c.event(settings, &e),
+        };
+        match action {
+            Some(Action::Save) => {
+                if let Err(err) = save::save(&ms_c.game.minesweeper, &save_file) {
+                    eprintln!("{}: {}", save_file.display(), err);
+                }
+            },
+            // a loaded game could bring options onto the daily board, or break up a time attack
+            Some(Action::Load) if ms_c.daily.is_some() || ms_c.time_attack.is_some() => {},
+            Some(Action::Load) => match save::load(&save_file) {
+                Ok(ms) => ms_c.game.replace(ms),
+                Err(err) => eprintln!("{}: {}", save_file.display(), err),
+            },
+            // a time attack is played on new beginner boards only
+            Some(_) if ms_c.time_attack.is_some() => {},
+            // switch difficulty, starting a new board, or that difficulty's board of the day
+            Some(action) => match (difficulty_for(action, difficulty), ms_c.daily) {
+                (Some(new_difficulty), Some(date)) => {
+                    ms_c.game.replace(MineSweeper::with_difficulty(new_difficulty, daily::seed(date)));
+                    daily::open(&mut ms_c.game);
+                },
+                (Some(new_difficulty), None) => ms_c.game.replace(new_board(new_difficulty, rand::random(), &options)),
+                (None, _) => {},
+            },
+            None => {},
+        }
+
+        // resize the window to fit a new difficulty or loaded game
+        let ms = &ms_c.game.minesweeper;
+        if (ms.rows, ms.cols, ms.topology) != (settings.rows, settings.cols, settings.topology) {
+            settings = MineSweeperViewSettings::new(ms.rows, ms.cols, ms.topology, 2.5);
+            ms_v.settings = settings;
+            window.set_size([settings.scr_width, settings.scr_height]);
+        }
+
+        // a reset, new difficulty or loaded game brings a new seed
+        let new_title = title(&ms_c, player.as_ref());
+        if new_title != current_title {
+            window.set_title(new_title.clone());
+            current_title = new_title;
+        }
 
         // handle rendering
         if let Some(r) = e.render_args() {
@@ -74,4 +447,9 @@ fn main() {
             });
         }
     }
+    if let (Some(path), Some(recorder)) = (&options.record, &ms_c.game.recorder) {
+        if let Err(err) = recorder.replay.save(path) {
+            eprintln!("{}: {}", path.display(), err);
+        }
+    }
 }
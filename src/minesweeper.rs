@@ -1,93 +1,76 @@
-use rand::{Rng, thread_rng};
-use std::ops::{Add, AddAssign};
-// use std::fmt;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-/// Contains all possible variants of what a certain square can hold
-#[derive(Clone, PartialEq)]
+use crate::analysis;
+use crate::solver;
+use crate::topology::{Neighbours, Topology};
+
+/// What a square holds: either mines of its own, or a number counting the mines around it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Content {
-    Zero,
-    One,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Mine,
-}
-
-// Autoimplements Into<Content> for u8
-impl From<u8> for Content {
-    fn from(n: u8) -> Self {
-        match n {
-            0 => Content::Zero,
-            1 => Content::One,
-            2 => Content::Two,
-            3 => Content::Three,
-            4 => Content::Four,
-            5 => Content::Five,
-            6 => Content::Six,
-            7 => Content::Seven,
-            8 => Content::Eight,
-            9 => Content::Mine,
-            _ => Content::Mine,
-        }
-    }
-}
-
-impl Into<u8> for  Content {
-    fn into(self) -> u8 {
-        match self {
-            Content::Zero => 0,
-            Content::One => 1,
-            Content::Two => 2,
-            Content::Three => 3,
-            Content::Four => 4,
-            Content::Five => 5,
-            Content::Six => 6,
-            Content::Seven => 7,
-            Content::Eight => 8,
-            Content::Mine => 9,
-        }
-    }
+    /// No mine, with this many mines in the squares around it between them
+    Number(u8),
+    /// This many mines, at least one
+    Mines(u8),
 }
 
 impl Default for Content {
-    fn default() -> Self { Content::Zero }
+    fn default() -> Self {
+        Content::Number(0)
+    }
 }
 
-impl Add for Content {
-    type Output = Content;
+impl Content {
+    pub fn is_mine(&self) -> bool {
+        matches!(self, Content::Mines(_))
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        (Into::<u8>::into(self) + Into::<u8>::into(rhs)).into()
+    /// How many mines the square holds
+    pub fn mines(&self) -> u8 {
+        match *self {
+            Content::Mines(mines) => mines,
+            Content::Number(_) => 0,
+        }
     }
-}
 
-impl AddAssign for Content {
-    fn add_assign(&mut self, rhs: Self) {
-        *self = self.clone() + rhs;
+    /// The number shown once the square is uncovered, unless it holds mines
+    pub fn number(&self) -> Option<u8> {
+        match *self {
+            Content::Number(number) => Some(number),
+            Content::Mines(_) => None,
+        }
     }
 }
 
 /// Contains all possible statuses of a square
-#[derive(PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     Uncovered,
+    #[default]
     Unmarked,
-    Flagged,
+    /// Flagged this many times, at most as many as a square can hold mines
+    Flagged(u8),
     Questioned,
 }
 
-impl Default for Status {
-    fn default() -> Self { Status::Unmarked }
+impl Status {
+    /// How many flags are on the square
+    pub fn flags(&self) -> u8 {
+        match *self {
+            Status::Flagged(flags) => flags,
+            _ => 0,
+        }
+    }
 }
 
 /// The struct containing information about each position on the game board
 /// Can either be `Uncovered` and show the `content` inside
 /// or `Flagged`, `Questioned`, or `Unmarked`.
-#[derive(Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Square {
     pub content: Content,
     pub status: Status,
@@ -95,143 +78,716 @@ pub struct Square {
 
 impl Square {
     fn new() -> Self {
-        Square { status: Status::Unmarked, content: Content::Zero }
+        Square { status: Status::Unmarked, content: Content::Number(0) }
+    }
+}
+
+/// Board sizes and mine counts of the original game, plus a user-chosen layout
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Difficulty {
+    /// 9x9 board with 10 mines
+    Beginner,
+    /// 16x16 board with 40 mines
+    Intermediate,
+    /// 16x30 board with 99 mines
+    #[default]
+    Expert,
+    Custom { rows: usize, cols: usize, mines: usize },
+}
+
+impl Difficulty {
+    pub fn rows(&self) -> usize {
+        match *self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 16,
+            Difficulty::Custom { rows, .. } => rows,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        match *self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 30,
+            Difficulty::Custom { cols, .. } => cols,
+        }
+    }
+
+    pub fn mines(&self) -> usize {
+        match *self {
+            Difficulty::Beginner => 10,
+            Difficulty::Intermediate => 40,
+            Difficulty::Expert => 99,
+            Difficulty::Custom { mines, .. } => mines,
+        }
+    }
+
+    /// The difficulty with this layout, named if it is one of the original game's
+    pub fn of(rows: usize, cols: usize, mines: usize) -> Self {
+        [Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Expert]
+            .iter()
+            .copied()
+            .find(|d| (d.rows(), d.cols(), d.mines()) == (rows, cols, mines))
+            .unwrap_or(Difficulty::Custom { rows, cols, mines })
+    }
+}
+
+/// Writes the name of the difficulty in the form it is parsed from
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Expert => write!(f, "expert"),
+            Difficulty::Custom { rows, cols, mines } => write!(f, "{}x{}x{}", rows, cols, mines),
+        }
+    }
+}
+
+/// Longest side a custom board can have, which keeps its number of squares well inside `usize`
+pub const MAX_SIDE: usize = 1000;
+
+/// Parses `beginner`, `intermediate`, `expert` or a custom `ROWSxCOLSxMINES` layout
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "beginner" => return Ok(Difficulty::Beginner),
+            "intermediate" => return Ok(Difficulty::Intermediate),
+            "expert" => return Ok(Difficulty::Expert),
+            _ => {},
+        }
+        let parts = s.split('x')
+            .map(|part| part.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid difficulty `{}`", s))?;
+        match parts[..] {
+            [rows, cols, _] if rows > MAX_SIDE || cols > MAX_SIDE => {
+                Err(format!("`{}` is too big, a side can be at most {} squares", s, MAX_SIDE))
+            },
+            [rows, cols, mines] if rows > 0 && cols > 0 && rows.checked_mul(cols).is_some_and(|squares| mines < squares) => {
+                Ok(Difficulty::Custom { rows, cols, mines })
+            },
+            [_, _, _] => Err(format!("`{}` needs at least one row, one column and one square without a mine", s)),
+            _ => Err(format!("invalid difficulty `{}`, expected ROWSxCOLSxMINES", s)),
+        }
+    }
+}
+
+/// Which squares are kept free of mines when the layout is generated on the first reveal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SafeStart {
+    /// Mines may be placed anywhere, even under the first click
+    None,
+    /// The first square revealed never holds a mine
+    Cell,
+    /// The first square revealed and all of its neighbours are mine-free, so it always opens up
+    #[default]
+    Opening,
+}
+
+/// Parses `none`, `cell` or `opening`
+impl FromStr for SafeStart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(SafeStart::None),
+            "cell" => Ok(SafeStart::Cell),
+            "opening" => Ok(SafeStart::Opening),
+            _ => Err(format!("invalid first click policy `{}`, expected none, cell or opening", s)),
+        }
     }
 }
 
-pub const ROWS: usize = 16;
-pub const COLS: usize = 31;
+/// How mine layouts are chosen once the first square is revealed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Generation {
+    /// Mines are scattered uniformly at random
+    #[default]
+    Random,
+    /// Layouts are regenerated until one can be cleared from the first click without guessing.
+    /// Gives up after `attempts` layouts or once `time_limit` has passed, keeping the layout
+    /// that logic got furthest into. Only the attempt budget keeps a seed reproducible.
+    NoGuess { attempts: usize, time_limit: Duration },
+}
+
+impl Generation {
+    /// Layouts tried by default before no-guess generation settles, enough to find
+    /// one for expert boards nearly every time
+    pub const NO_GUESS_ATTEMPTS: usize = 2000;
 
-/// The type of array used to store all the squares
-type Field = [[Square; COLS]; ROWS]; 
+    /// No-guess generation with the default budget, cut short after two seconds
+    pub fn no_guess() -> Self {
+        Generation::NoGuess { attempts: Self::NO_GUESS_ATTEMPTS, time_limit: Duration::from_secs(2) }
+    }
 
+    /// No-guess generation limited only by the number of layouts tried,
+    /// so the same seed gives the same board however fast the machine is
+    pub fn no_guess_attempts(attempts: usize) -> Self {
+        Generation::NoGuess { attempts, time_limit: Duration::MAX }
+    }
+}
+
+/// The type of grid used to store all the squares, indexed `field[row][col]`
+pub type Field = Vec<Vec<Square>>;
+
+/// Layouts tried when looking for one in a 3BV range, without no-guess generation
+const BBBV_ATTEMPTS: usize = 1000;
+
+/// Most mines one square can hold, in the multimines variant
+pub const MAX_PER_SQUARE: u8 = 3;
+
+/// Parses how many mines one square can hold, from one to `MAX_PER_SQUARE`
+pub fn parse_per_square(s: &str) -> Result<u8, String> {
+    s.parse()
+        .ok()
+        .filter(|per_square| (1..=MAX_PER_SQUARE).contains(per_square))
+        .ok_or_else(|| format!("invalid mines per square `{}`, expected 1 to {}", s, MAX_PER_SQUARE))
+}
+
+/// The highest value the three-digit header displays can show
+pub const MAX_DISPLAY: u32 = 999;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MineSweeper {
     pub field: Field,
     pub rows: usize,
     pub cols: usize,
+    pub mines: usize,
     pub lost: bool,
     pub won: bool,
     pub left: usize,
+    /// Number of flags placed, with every mine hit counting as flagged
+    pub flags: usize,
+    /// Whether the first square has been revealed and the clock is running
+    pub started: bool,
+    /// Seconds played so far
+    pub time: f64,
+    /// How the first reveal is protected from mines
+    pub safe_start: SafeStart,
+    /// Whether mines have been placed yet, which happens on the first reveal
+    pub generated: bool,
+    /// How the mine layout is chosen
+    pub generation: Generation,
+    /// Seed of the random number generator placing the mines
+    pub seed: u64,
+    /// Whether marking cycles through a question mark after the flag
+    #[serde(default = "question_marks_default")]
+    pub question_marks: bool,
+    /// Clicks made so far, wasted ones included
+    #[serde(default)]
+    pub clicks: usize,
+    /// Inclusive range of 3BV the generated layout should fall in, if any
+    #[serde(default)]
+    pub bbbv_range: Option<(usize, usize)>,
+    /// Moves taken back so far; a game won with any isn't a real win
+    #[serde(default)]
+    pub undos: usize,
+    /// Which squares touch which
+    #[serde(default)]
+    pub topology: Topology,
+    /// Most mines one square can hold; above one, numbers count every mine
+    /// around them and a square takes as many flags as it can hold mines
+    #[serde(default = "per_square_default")]
+    pub per_square: u8,
+    /// Squares with mines that can be uncovered before the game is lost; one is the usual game
+    #[serde(default = "lives_default")]
+    pub lives: usize,
+    /// Squares with mines uncovered so far, each of which took a life
+    #[serde(default)]
+    pub hits: usize,
+}
+
+/// Saves from before mines could share a square had one to a square
+fn per_square_default() -> u8 {
+    1
+}
+
+/// Saves from before lives mode had the one life of the usual game
+fn lives_default() -> usize {
+    1
+}
+
+/// Saves from before question marks could be turned off always had them
+fn question_marks_default() -> bool {
+    true
 }
 
 impl MineSweeper {
-    pub fn new() -> Self {
-        let mut field: Field = Default::default();
-        for i in 0..ROWS {
-            for j in 0..COLS {
-                field[i][j] = Square::new();
-            }
-        }
-        let bombs = 99;
-        Self::populate(&mut field, bombs);
-        Self::calculate(&mut field);
+    /// Creates a `rows` by `cols` board that will hold `mines` mines.
+    /// The mines are only placed by `generate` once the first square is revealed;
+    /// the same `seed` and first square always give the same layout.
+    pub fn new(rows: usize, cols: usize, mines: usize, seed: u64) -> Self {
+        assert!(rows > 0 && cols > 0, "board must have at least one square");
+        assert!(rows.checked_mul(cols).is_some_and(|squares| mines < squares), "board must have at least one square without a mine");
         MineSweeper {
-            field,
-            rows: ROWS,
-            cols: COLS,
+            field: empty_field(rows, cols),
+            rows,
+            cols,
+            mines,
             lost: false,
             won: false,
-            left: ROWS * COLS - bombs,
+            left: rows * cols - mines,
+            flags: 0,
+            started: false,
+            time: 0.0,
+            safe_start: SafeStart::default(),
+            generated: false,
+            generation: Generation::default(),
+            seed,
+            question_marks: true,
+            clicks: 0,
+            bbbv_range: None,
+            undos: 0,
+            topology: Topology::default(),
+            per_square: 1,
+            lives: 1,
+            hits: 0,
         }
     }
 
-    /// Retrieves a mutalble reference to a square
+    /// A new game of the same size and with the same options, generated from `seed`
+    pub fn fresh(&self, seed: u64) -> Self {
+        let mut ms = Self::new(self.rows, self.cols, self.mines, seed);
+        ms.safe_start = self.safe_start;
+        ms.generation = self.generation;
+        ms.question_marks = self.question_marks;
+        ms.bbbv_range = self.bbbv_range;
+        ms.topology = self.topology;
+        ms.per_square = self.per_square;
+        ms.lives = self.lives;
+        ms
+    }
+
+    /// Creates a board with the size and mine count of a `Difficulty`
+    pub fn with_difficulty(difficulty: Difficulty, seed: u64) -> Self {
+        Self::new(difficulty.rows(), difficulty.cols(), difficulty.mines(), seed)
+    }
+
+    /// Retrieves an immutable reference to a square
     pub fn get<T: Into<usize>>(&self, row: T, col: T) -> &Square {
         &self.field[row.into()][col.into()]
     }
 
-    /// Retrieves an immutable reference to a square
+    /// Retrieves a mutable reference to a square
     pub fn get_mut<T: Into<usize>>(&mut self, row: T, col: T) -> &mut Square {
         &mut self.field[row.into()][col.into()]
     }
 
-    fn populate(field: &mut Field, bombs: usize) {
+    /// Positions of all squares touching `(row, col)`
+    pub fn neighbours(&self, row: usize, col: usize) -> Neighbours {
+        self.topology.neighbours(self.rows, self.cols, row, col)
+    }
+
+    /// Mines not yet accounted for by a flag, negative if too many flags were placed
+    pub fn mines_left(&self) -> i64 {
+        self.mines as i64 - self.flags as i64
+    }
+
+    /// Advances the clock by `dt` seconds while a game is in progress
+    pub fn tick(&mut self, dt: f64) {
+        if self.started && !self.won && !self.lost {
+            self.time += dt;
+        }
+    }
+
+    /// Whole seconds played, capped to what the timer display can show
+    pub fn seconds(&self) -> u32 {
+        (self.time as u32).min(MAX_DISPLAY)
+    }
+
+    /// Places the mines and numbers around the first square revealed, `(row, col)`,
+    /// keeping it clear according to `safe_start`. Layouts are regenerated while they
+    /// need guessing under no-guess generation, or fall outside `bbbv_range`.
+    /// The solver counts one mine to a square, so boards stacking mines are never
+    /// checked for guessing.
+    pub fn generate(&mut self, row: usize, col: usize) {
+        let safe = self.safe_squares(row, col);
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let (attempts, time_limit, no_guess) = match self.generation {
+            Generation::NoGuess { attempts, time_limit } if self.per_square == 1 => (attempts, Some(time_limit), true),
+            _ if self.bbbv_range.is_some() => (BBBV_ATTEMPTS, None, false),
+            _ => (1, None, false),
+        };
+
+        let start = Instant::now();
+        // how far each layout falls short: squares logic can't reach, then distance from the 3BV range
+        let mut best: Option<((usize, usize), Field)> = None;
+        for _ in 0..attempts.max(1) {
+            let mut field = empty_field(self.rows, self.cols);
+            Self::populate(&mut field, self.mines, self.per_square, &mut rng, &safe);
+            Self::calculate(&mut field, self.topology);
+            let unsolved = if no_guess { solver::unsolved(&field, self.topology, row, col) } else { 0 };
+            let off_range = match self.bbbv_range {
+                Some((min, max)) => {
+                    let bbbv = analysis::bbbv(&field, self.topology);
+                    min.saturating_sub(bbbv) + bbbv.saturating_sub(max)
+                },
+                None => 0,
+            };
+            let shortfall = (unsolved, off_range);
+            if best.as_ref().is_none_or(|(least, _)| shortfall < *least) {
+                best = Some((shortfall, field));
+            }
+            if shortfall == (0, 0) || time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+                break;
+            }
+        }
+        if let Some((_, mut field)) = best {
+            // keep any marks placed before the first reveal
+            for (squares, old) in field.iter_mut().zip(&self.field) {
+                for (square, old) in squares.iter_mut().zip(old) {
+                    square.status = old.status.clone();
+                }
+            }
+            self.field = field;
+        }
+        self.count_left();
+        self.generated = true;
+    }
+
+    /// Places mines exactly at `mines` instead of generating a layout,
+    /// stacking a mine for each time a position is given.
+    /// Panics if that stacks more than `per_square` mines on a square.
+    pub fn place_mines(&mut self, mines: &[(usize, usize)]) {
+        self.field = empty_field(self.rows, self.cols);
+        for &(row, col) in mines {
+            let content = &mut self.field[row][col].content;
+            assert!(content.mines() < self.per_square, "square {}, {} can't hold another mine", row, col);
+            *content = Content::Mines(content.mines() + 1);
+        }
+        Self::calculate(&mut self.field, self.topology);
+        self.count_left();
+        self.generated = true;
+    }
+
+    /// Positions of every mine, row by row, a square given once for each mine it holds
+    pub fn layout(&self) -> Vec<(usize, usize)> {
+        let mut mines = vec![];
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                for _ in 0..self.field[row][col].content.mines() {
+                    mines.push((row, col));
+                }
+            }
+        }
+        mines
+    }
+
+    /// Counts the squares left to clear on a newly laid out board, which
+    /// can't be known beforehand when several mines may share a square
+    fn count_left(&mut self) {
+        self.left = self.field.iter().flatten().filter(|square| !square.content.is_mine()).count();
+    }
+
+    /// The squares `safe_start` keeps clear for a first reveal at `(row, col)`.
+    /// Falls back to a weaker policy when the board is too crowded to honour it.
+    fn safe_squares(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut safe = match self.safe_start {
+            SafeStart::None => vec![],
+            SafeStart::Cell => vec![(row, col)],
+            SafeStart::Opening => {
+                let mut safe: Vec<_> = self.neighbours(row, col).collect();
+                safe.push((row, col));
+                safe
+            },
+        };
+        if self.rows * self.cols - safe.len() < self.mines {
+            safe = vec![(row, col)];
+        }
+        safe
+    }
+
+    /// Drops `bombs` mines one at a time on random squares outside `safe`,
+    /// skipping squares already holding `per_square` of them
+    fn populate(field: &mut Field, bombs: usize, per_square: u8, rng: &mut ChaCha8Rng, safe: &[(usize, usize)]) {
         let mut mines = bombs;
-        let mut rng = thread_rng();
-        let empty = Square::new();
         while mines > 0 {
             let (row, col) = (rng.gen_range(0, field.len()), rng.gen_range(0, field[0].len()));
-            if field[row][col] == empty {
-                field[row][col].content = Content::Mine;
+            // squares flagged before the first reveal can still hold mines
+            let held = field[row][col].content.mines();
+            if held < per_square && !safe.contains(&(row, col)) {
+                field[row][col].content = Content::Mines(held + 1);
                 mines -= 1;
             }
         }
     }
 
-    fn calculate(field: &mut Field) {
-
-        // middle section
-        for i in 1..(ROWS-1) {
-            for j in 1..(COLS-1) {
-               if field[i][j].content == Content::Mine {
-                   field[i-1][j-1].content += 1.into();
-                   field[i-1][j].content += 1.into();
-                   field[i-1][j+1].content += 1.into();
-                   field[i][j-1].content += 1.into();
-                   field[i][j+1].content += 1.into();
-                   field[i+1][j-1].content += 1.into();
-                   field[i+1][j].content += 1.into();
-                   field[i+1][j+1].content += 1.into();
+    /// Numbers every square without mines by the mines around it
+    fn calculate(field: &mut Field, topology: Topology) {
+        let rows = field.len();
+        let cols = field[0].len();
+        for i in 0..rows {
+            for j in 0..cols {
+                let mines = field[i][j].content.mines();
+                if mines == 0 {
+                    continue;
+                }
+                for (r, c) in topology.neighbours(rows, cols, i, j) {
+                    if let Content::Number(number) = &mut field[r][c].content {
+                        *number += mines;
+                    }
                 }
             }
         }
+    }
+}
 
-        // left and right
-        for i in 1..(ROWS-1) {
-            if field[i][0].content == Content::Mine {
-                field[i-1][0].content += 1.into();
-                field[i-1][1].content += 1.into();
-                field[i][1].content += 1.into();
-                field[i+1][0].content += 1.into();
-                field[i+1][1].content += 1.into();
-            }
-            if field[i][COLS-1].content == Content::Mine {
-                field[i-1][COLS-2].content += 1.into();
-                field[i-1][COLS-1].content += 1.into();
-                field[i][COLS-2].content += 1.into();
-                field[i+1][COLS-2].content += 1.into();
-                field[i+1][COLS-1].content += 1.into();
-            }
+/// A `rows` by `cols` grid of unmarked squares without mines
+fn empty_field(rows: usize, cols: usize) -> Field {
+    (0..rows)
+        .map(|_| (0..cols).map(|_| Square::new()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_is_sized_at_runtime() {
+        let mut ms = MineSweeper::new(5, 12, 7, 0);
+        ms.generate(2, 6);
+        assert_eq!(ms.field.len(), 5);
+        assert!(ms.field.iter().all(|row| row.len() == 12));
+        let mines = ms.field.iter().flatten().filter(|square| square.content.is_mine()).count();
+        assert_eq!((mines, ms.left), (7, 5 * 12 - 7));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one square without a mine")]
+    fn board_needs_a_square_without_a_mine() {
+        MineSweeper::new(3, 3, 9, 0);
+    }
+
+    #[test]
+    fn difficulties_parse_from_names_and_layouts() {
+        assert_eq!("beginner".parse(), Ok(Difficulty::Beginner));
+        assert_eq!("Expert".parse(), Ok(Difficulty::Expert));
+        assert_eq!("20x40x150".parse(), Ok(Difficulty::Custom { rows: 20, cols: 40, mines: 150 }));
+        for bad in &["hard", "9x9", "9x9x9x9", "9xnx10", "0x9x1", "9x0x1", "3x3x9", ""] {
+            assert!(bad.parse::<Difficulty>().is_err(), "{} parsed", bad);
         }
+        let custom = MineSweeper::with_difficulty("4x6x5".parse().unwrap(), 0);
+        assert_eq!((custom.rows, custom.cols, custom.mines), (4, 6, 5));
+    }
+
+    #[test]
+    fn oversized_difficulties_are_refused() {
+        assert!("99999999999x99999999999x1".parse::<Difficulty>().is_err());
+        assert!("1001x9x10".parse::<Difficulty>().is_err());
+        assert!("9x1001x10".parse::<Difficulty>().is_err());
+        assert_eq!("1000x1000x1".parse(), Ok(Difficulty::Custom { rows: 1000, cols: 1000, mines: 1 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one square without a mine")]
+    fn boards_too_big_to_count_are_refused() {
+        MineSweeper::new(usize::MAX, 2, 1, 0);
+    }
+
+    #[test]
+    fn neighbours_stay_on_the_board() {
+        let ms = MineSweeper::new(5, 12, 7, 0);
+        assert_eq!(ms.neighbours(0, 0).collect::<Vec<_>>(), vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(ms.neighbours(4, 11).count(), 3);
+        assert_eq!(ms.neighbours(0, 5).count(), 5);
+        assert_eq!(ms.neighbours(2, 5).count(), 8);
+        assert_eq!(MineSweeper::new(1, 2, 0, 0).neighbours(0, 0).count(), 1);
+    }
+
+    #[test]
+    fn clock_waits_for_first_reveal() {
+        let mut ms = MineSweeper::new(9, 9, 10, 0);
+        ms.tick(5.0);
+        assert_eq!(ms.seconds(), 0);
+        ms.started = true;
+        ms.tick(2.5);
+        ms.tick(1.0);
+        assert_eq!(ms.seconds(), 3);
+    }
+
+    #[test]
+    fn clock_stops_when_game_ends() {
+        let mut ms = MineSweeper::new(9, 9, 10, 0);
+        ms.started = true;
+        ms.tick(4.0);
+        ms.lost = true;
+        ms.tick(10.0);
+        assert_eq!(ms.seconds(), 4);
+    }
 
-        // top and bottom
-        for j in 1..30 {
-            if field[0][j].content == Content::Mine {
-                field[0][j-1].content += 1.into();
-                field[0][j+1].content += 1.into();
-                field[1][j-1].content += 1.into();
-                field[1][j].content += 1.into();
-                field[1][j+1].content += 1.into();
-            } 
-            if field[ROWS-1][j].content == Content::Mine {
-                field[ROWS-2][j-1].content += 1.into();
-                field[ROWS-2][j].content += 1.into();
-                field[ROWS-2][j+1].content += 1.into();
-                field[ROWS-1][j-1].content += 1.into();
-                field[ROWS-1][j+1].content += 1.into();
-            } 
-        }
-
-        // edges
-        if field[0][0].content == Content::Mine {
-            field[0][1].content += 1.into();
-            field[1][1].content += 1.into();
-            field[1][0].content += 1.into();
-        }
-        if field[0][COLS-1].content == Content::Mine {
-            field[0][COLS-2].content += 1.into();
-            field[1][COLS-2].content += 1.into();
-            field[1][COLS-1].content += 1.into();
-        }
-        if field[ROWS-1][0].content == Content::Mine {
-            field[ROWS-1][1].content += 1.into();
-            field[ROWS-2][1].content += 1.into();
-            field[ROWS-2][0].content += 1.into();
-        }
-        if field[ROWS-1][COLS-1].content == Content::Mine {
-            field[ROWS-1][COLS-2].content += 1.into();
-            field[ROWS-2][COLS-2].content += 1.into();
-            field[ROWS-2][COLS-1].content += 1.into();
+    #[test]
+    fn clock_caps_at_display_limit() {
+        let mut ms = MineSweeper::new(9, 9, 10, 0);
+        ms.started = true;
+        ms.tick(5000.0);
+        assert_eq!(ms.seconds(), MAX_DISPLAY);
+    }
+
+    fn mines_at(ms: &MineSweeper, positions: &[(usize, usize)]) -> usize {
+        positions.iter()
+            .filter(|&&(row, col)| ms.get(row, col).content.is_mine())
+            .count()
+    }
+
+    fn mine_count(ms: &MineSweeper) -> usize {
+        ms.field.iter().flatten().map(|square| square.content.mines() as usize).sum()
+    }
+
+    #[test]
+    fn mines_wait_for_first_reveal() {
+        let mut ms = MineSweeper::new(9, 9, 10, 0);
+        assert!(!ms.generated);
+        assert_eq!(mine_count(&ms), 0);
+        ms.generate(4, 4);
+        assert!(ms.generated);
+        assert_eq!(mine_count(&ms), 10);
+    }
+
+    #[test]
+    fn safe_cell_keeps_first_square_clear() {
+        for seed in 0..50 {
+            let mut ms = MineSweeper::new(3, 3, 8, seed);
+            ms.safe_start = SafeStart::Cell;
+            ms.generate(0, 0);
+            assert_eq!(mines_at(&ms, &[(0, 0)]), 0);
+        }
+    }
+
+    #[test]
+    fn safe_opening_keeps_neighbourhood_clear() {
+        for seed in 0..50 {
+            let mut ms = MineSweeper::new(9, 9, 70, seed);
+            ms.safe_start = SafeStart::Opening;
+            ms.generate(4, 4);
+            let mut around: Vec<_> = ms.neighbours(4, 4).collect();
+            around.push((4, 4));
+            assert_eq!(mines_at(&ms, &around), 0);
+            assert_eq!(ms.get(4usize, 4usize).content, Content::Number(0));
+        }
+    }
+
+    #[test]
+    fn mines_go_under_squares_flagged_before_first_reveal() {
+        let mut ms = MineSweeper::new(3, 3, 8, 0);
+        for square in ms.field.iter_mut().flatten() {
+            square.status = Status::Flagged(1);
+        }
+        ms.generate(1, 1);
+        assert_eq!(mines_at(&ms, &[(1, 1)]), 0);
+        assert_eq!(mine_count(&ms), 8);
+    }
+
+    #[test]
+    fn crowded_opening_falls_back_to_safe_cell() {
+        let mut ms = MineSweeper::new(3, 3, 8, 0);
+        ms.safe_start = SafeStart::Opening;
+        ms.generate(1, 1);
+        assert_eq!(mines_at(&ms, &[(1, 1)]), 0);
+        assert_eq!(mine_count(&ms), 8);
+    }
+
+    #[test]
+    fn same_seed_and_first_click_give_same_layout() {
+        let mut a = MineSweeper::with_difficulty(Difficulty::Expert, 1234);
+        let mut b = MineSweeper::with_difficulty(Difficulty::Expert, 1234);
+        a.generate(7, 12);
+        b.generate(7, 12);
+        assert_eq!(a.layout(), b.layout());
+
+        let mut c = MineSweeper::with_difficulty(Difficulty::Expert, 1235);
+        c.generate(7, 12);
+        assert_ne!(a.layout(), c.layout());
+    }
+
+    #[test]
+    fn seeded_layout_is_stable() {
+        // shared seeds must keep producing the same board across releases
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Beginner, 42);
+        ms.generate(4, 4);
+        assert_eq!(ms.layout(), vec![(1, 1), (1, 7), (2, 4), (2, 7), (6, 0), (6, 5), (6, 6), (6, 8), (7, 0), (8, 5)]);
+    }
+
+    #[test]
+    fn mines_left_goes_negative() {
+        let mut ms = MineSweeper::new(9, 9, 10, 0);
+        assert_eq!(ms.mines_left(), 10);
+        ms.flags = 12;
+        assert_eq!(ms.mines_left(), -2);
+    }
+
+    #[test]
+    fn generation_honours_bbbv_range() {
+        for seed in 0..5 {
+            let mut ms = MineSweeper::with_difficulty(Difficulty::Beginner, seed);
+            ms.bbbv_range = Some((22, 24));
+            ms.generate(4, 4);
+            let bbbv = crate::analysis::bbbv(&ms.field, ms.topology);
+            assert!((22..=24).contains(&bbbv), "seed {} gave 3BV {}", seed, bbbv);
+        }
+    }
+
+    #[test]
+    fn torus_numbers_count_across_the_edges() {
+        let mut ms = MineSweeper::new(9, 9, 1, 0);
+        ms.topology = Topology::Torus;
+        ms.place_mines(&[(0, 0)]);
+        for &(row, col) in &[(8usize, 8usize), (8, 0), (0, 8), (1, 1)] {
+            assert_eq!(ms.get(row, col).content, Content::Number(1));
+        }
+        assert_eq!(ms.get(7usize, 7usize).content, Content::Number(0));
+    }
+
+    #[test]
+    fn generation_stacks_mines_up_to_the_limit() {
+        for seed in 0..20 {
+            // more mines than squares outside the opening, so some have to share
+            let mut ms = MineSweeper::new(5, 5, 20, seed);
+            ms.per_square = 3;
+            ms.generate(2, 2);
+            assert_eq!(mine_count(&ms), 20);
+            assert!(ms.field.iter().flatten().all(|square| square.content.mines() <= 3));
+            assert!(ms.field.iter().flatten().any(|square| square.content.mines() > 1));
+            assert_eq!(ms.left, 25 - ms.field.iter().flatten().filter(|square| square.content.is_mine()).count());
+            assert_eq!(ms.layout().len(), 20);
+        }
+    }
+
+    #[test]
+    fn numbers_count_every_stacked_mine() {
+        let mut ms = MineSweeper::new(3, 3, 8, 0);
+        ms.per_square = 2;
+        let around: Vec<_> = ms.neighbours(1, 1).collect();
+        let mines: Vec<_> = around.iter().chain(&around).copied().collect();
+        ms.mines = mines.len();
+        ms.place_mines(&mines);
+        assert_eq!(ms.get(1usize, 1usize).content, Content::Number(16));
+        assert_eq!(ms.get(0usize, 0usize).content, Content::Mines(2));
+        assert_eq!(ms.left, 1);
+
+        // the layout gives each square once per mine, so it places the same board again
+        let field = ms.field.clone();
+        ms.place_mines(&ms.layout());
+        assert_eq!(ms.field, field);
+    }
+
+    #[test]
+    fn difficulty_names_round_trip() {
+        for difficulty in [Difficulty::Beginner, Difficulty::Expert, Difficulty::Custom { rows: 5, cols: 8, mines: 3 }] {
+            assert_eq!(difficulty.to_string().parse(), Ok(difficulty));
         }
+        assert_eq!(Difficulty::of(16, 16, 40), Difficulty::Intermediate);
+    }
+
+    #[test]
+    fn marks_survive_generation() {
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Beginner, 1);
+        ms.get_mut(0usize, 0usize).status = Status::Flagged(1);
+        ms.generate(4, 4);
+        assert_eq!(ms.get(0usize, 0usize).status, Status::Flagged(1));
     }
 }
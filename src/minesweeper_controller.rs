@@ -1,197 +1,542 @@
-use piston::input::GenericEvent;
+use std::path::PathBuf;
 
-use crate::MineSweeper;
-use crate::minesweeper::{Content, Status};
+use chrono::{Local, NaiveDate};
+use piston::input::{Button, GenericEvent};
+
+use minesweeper::{daily, Field, Game, MineSweeper, Outcome, Status};
+use minesweeper::solver::{self, Hint};
+use minesweeper::stats::{Finished, Stats};
+use minesweeper::time_attack::TimeAttack;
+
+use crate::bindings::{Action, Bindings};
 use crate::MineSweeperViewSettings;
 
 /// Handles all events, communicating between input and data
 pub struct MineSweeperController {
-    pub minesweeper: MineSweeper,
+    pub game: Game,
+    pub bindings: Bindings,
     pub cursor_pos: [f64; 2],
+    /// Square picked with the keyboard, which key presses act on once it has been moved
+    pub selected: (usize, usize),
+    /// Whether the keyboard was used last, so key presses act on `selected`
+    /// rather than the square under the mouse
+    pub keyboard: bool,
+    /// Whether the clock is stopped and the board hidden
+    pub paused: bool,
+    /// Mouse buttons bound to reveal, flag and chord held down since being pressed on the board
+    pub reveal_held: bool,
+    pub flag_held: bool,
+    pub chord_held: bool,
+    /// Whether the reveal button went down on the smiley and hasn't come back up
+    pub smiley_held: bool,
+    /// The square the solver pointed out, shown until the next press
+    pub hint: Option<Hint>,
+    /// Whether the chance of a mine is shown on each covered square
+    pub heatmap: bool,
+    /// The chance of a mine on each square, worked out for `odds_field`
+    pub odds: Option<Vec<Vec<Option<f64>>>>,
+    odds_field: Field,
+    /// Every game finished, which each newly finished game is added to
+    pub stats: Stats,
+    /// Where `stats` are written after each game, if anywhere
+    pub stats_file: Option<PathBuf>,
+    /// The name best times are recorded under
+    pub name: String,
+    /// Whether the best times are shown in place of the board, with the clock stopped
+    pub best_times: bool,
+    /// The day whose board is being played, if playing the daily board
+    pub daily: Option<NaiveDate>,
+    /// Daily wins, which each clean win on the daily board is added to
+    pub leaderboard: Stats,
+    /// Where `leaderboard` is kept, read again before each win is added since others may share it
+    pub leaderboard_file: Option<PathBuf>,
+    /// The daily win this game put on the leaderboard, taken back off if its last move is undone
+    posted: Option<Finished>,
+    /// The run against the clock, if playing a time attack
+    pub time_attack: Option<TimeAttack>,
+}
+
+/// The covered squares held down by a press on `(row, col)`, which takes in
+/// its neighbours when `chording`. Flagged and uncovered squares stay put.
+fn held_squares(ms: &MineSweeper, (row, col): (usize, usize), chording: bool) -> Vec<(usize, usize)> {
+    let mut squares = vec![(row, col)];
+    if chording {
+        squares.extend(ms.neighbours(row, col));
+    }
+    squares.retain(|&(r, c)| {
+        let status = &ms.get(r, c).status;
+        status == &Status::Unmarked || status == &Status::Questioned
+    });
+    squares
 }
 
 impl MineSweeperController {
-    pub fn new(minesweeper: MineSweeper) -> Self {
-        MineSweeperController { 
-            minesweeper,
+    pub fn new(game: Game, bindings: Bindings) -> Self {
+        MineSweeperController {
+            game,
+            bindings,
             cursor_pos: [0.0; 2],
+            selected: (0, 0),
+            keyboard: false,
+            paused: false,
+            reveal_held: false,
+            flag_held: false,
+            chord_held: false,
+            smiley_held: false,
+            hint: None,
+            heatmap: false,
+            odds: None,
+            odds_field: vec![],
+            stats: Stats::default(),
+            stats_file: None,
+            name: String::new(),
+            best_times: false,
+            daily: None,
+            leaderboard: Stats::default(),
+            leaderboard_file: None,
+            posted: None,
+            time_attack: None,
         }
     }
 
-    /// Handles an event
-    pub fn event<E: GenericEvent>(&mut self, settings: MineSweeperViewSettings, e: &E) {
-        use piston::input::{Button, Key, MouseButton};
-    
-        let offset = [
-            settings.border_long,
-            settings.border_long * 2.0 + settings.smiley_side,
-        ];
-        let size = [
-            settings.cols as f64 * settings.square_side,
-            settings.rows as f64 * settings.square_side,
-        ];
+    /// Whether a chord is being held down, with its own button or reveal and flag together
+    pub fn chording(&self) -> bool {
+        self.chord_held || (self.reveal_held && self.flag_held)
+    }
+
+    /// Whether any mouse button is held down on the board
+    pub fn pressing(&self) -> bool {
+        self.reveal_held || self.flag_held || self.chord_held
+    }
+
+    /// The covered squares drawn pushed in under a held button:
+    /// the square under the cursor, and its neighbours too while chording
+    pub fn pressed_squares(&self, settings: MineSweeperViewSettings) -> Vec<(usize, usize)> {
+        match self.square_at_cursor(settings) {
+            Some(square) if self.reveal_held || self.chord_held => held_squares(&self.game.minesweeper, square, self.chording()),
+            _ => vec![],
+        }
+    }
 
+    /// Whether the cursor is over the smiley
+    pub fn over_smiley(&self, settings: MineSweeperViewSettings) -> bool {
+        let x = self.cursor_pos[0];
+        let y = self.cursor_pos[1];
+        let mid = settings.scr_width/2.0;
+        let half_smile = settings.smiley_side/2.0;
+        x >= mid - half_smile && x <= mid + half_smile && y >= settings.border_long && y <= settings.border_long + settings.smiley_side
+    }
+
+    /// The board square under the cursor, if any.
+    /// Rows are found first, since the odd rows of a hex board are shifted along.
+    pub fn square_at_cursor(&self, settings: MineSweeperViewSettings) -> Option<(usize, usize)> {
+        let top = settings.border_long * 2.0 + settings.smiley_side;
+        let y = self.cursor_pos[1] - top;
+        if y < 1.0 || y >= settings.board_height {
+            return None;
+        }
+        let row = (y / settings.square_side) as usize;
+        let x = self.cursor_pos[0] - settings.border_long - settings.row_shift(row);
+        if x < 0.0 || x >= settings.cols as f64 * settings.square_side {
+            return None;
+        }
+        Some((row, (x / settings.square_side) as usize))
+    }
+
+    /// Handles an event, returning any action that reaches beyond this game
+    /// (saving, loading or changing difficulty) for the caller to carry out.
+    /// Like the original game, a click bound to reveal takes effect when the button
+    /// comes back up, a click bound to flag takes effect straight away, and a chord,
+    /// with its own button or reveal and flag together, when released. Presses and
+    /// releases are tracked separately so the view can show what is held down.
+    pub fn event<E: GenericEvent>(&mut self, settings: MineSweeperViewSettings, e: &E) -> Option<Action> {
         if let Some(p) = e.mouse_cursor_args() {
             self.cursor_pos = p;
+            self.keyboard = false;
         }
 
-        if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
-            let x = self.cursor_pos[0];
-            let y = self.cursor_pos[1];
-            let mid = settings.scr_width/2.0;
-            let half_smile = settings.smiley_side/2.0;
-            if x >= mid - half_smile && x <= mid + half_smile && y >= settings.border_long && y <= settings.border_long + settings.smiley_side {
+        if let Some(args) = e.update_args() {
+            if !self.paused && !self.best_times {
+                self.game.tick(args.dt);
+                if let Some(time_attack) = &mut self.time_attack {
+                    time_attack.advance(&mut self.game, args.dt);
+                }
+            }
+        }
+
+        let pressed = match e.press_args() {
+            Some(Button::Keyboard(key)) => self.bindings.key(key).map(|action| (action, false)),
+            Some(Button::Mouse(button)) => self.bindings.mouse(button).map(|action| (action, true)),
+            _ => None,
+        };
+        let released = match e.release_args() {
+            Some(Button::Mouse(button)) => self.bindings.mouse(button),
+            _ => None,
+        };
+
+        if pressed.is_some() {
+            self.hint = None;
+        }
+
+        if self.paused {
+            if let Some((Action::Pause, _)) = pressed {
+                self.paused = false;
+            }
+            return None;
+        }
+        if self.best_times {
+            if let Some((Action::BestTimes, _)) = pressed {
+                self.best_times = false;
+            }
+            return None;
+        }
+        // once the time is up only starting over does anything
+        if self.time_attack.as_ref().is_some_and(TimeAttack::over) {
+            self.reveal_held = false;
+            self.flag_held = false;
+            self.chord_held = false;
+            self.smiley_held = false;
+            if let Some((Action::Reset, _)) = pressed {
+                self.reset();
+            }
+            return None;
+        }
+        let playing = self.game.state() == Outcome::Playing;
+
+        // the smiley starts over when a click on it is released over it
+        if let Some((Action::Reveal, true)) = pressed {
+            if self.over_smiley(settings) {
+                self.smiley_held = true;
+                return None;
+            }
+        }
+        if let (true, Some(Action::Reveal)) = (self.smiley_held, released) {
+            self.smiley_held = false;
+            if self.over_smiley(settings) {
                 self.reset();
-                return;
             }
+            return None;
         }
 
-        if self.minesweeper.lost || self.minesweeper.won { return }
+        let result = match pressed {
+            Some((action, true)) => self.mouse_pressed(action, settings),
+            Some((action, false)) => self.key_pressed(action, settings),
+            None => None,
+        };
+        if let Some(action) = released {
+            self.mouse_released(action, settings);
+        }
 
-        if let Some(Button::Keyboard(key)) = e.press_args() {
-            match key {
-                Key::Space => {
-                    let x = self.cursor_pos[0] - offset[0];
-                    let y = self.cursor_pos[1] - offset[1];
-        
-                    if x >= 0.0 && x < size[0] && y >= 1.0 && y < size[1] { 
-                        let col = (x / size[0] * self.minesweeper.cols as f64) as usize;
-                        let row = (y / size[1] * self.minesweeper.rows as f64) as usize;
-                        self.mark(row, col);
-                    }
-                },
-                _ => {},
+        // track the buttons even once the game is over, but don't leave any stuck down
+        if self.game.state() != Outcome::Playing {
+            self.reveal_held = false;
+            self.flag_held = false;
+            self.chord_held = false;
+            if playing {
+                self.record_finished();
             }
         }
 
-        if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
-            let x = self.cursor_pos[0] - offset[0];
-            let y = self.cursor_pos[1] - offset[1];
+        if self.heatmap {
+            self.refresh_odds();
+        }
+        result
+    }
 
-            if x >= 0.0 && x < size[0] && y >= 1.0 && y < size[1] { 
-                let col = (x / size[0] * self.minesweeper.cols as f64) as usize;
-                let row = (y / size[1] * self.minesweeper.rows as f64) as usize;
-                let ref status = self.minesweeper.get(row, col).status;
-                if status == &Status::Unmarked  || status == &Status::Questioned {
-                    self.reveal(row, col);
-                } else if status == &Status::Uncovered {
-                    self.clear_around(row, col);
+    /// Adds the game just finished to the statistics, and writes them out.
+    /// Boards played against the clock are left out, being scored by the run instead,
+    /// and a clean win on the daily board goes on the leaderboard as well.
+    fn record_finished(&mut self) {
+        self.posted = None;
+        if self.time_attack.is_some() {
+            return;
+        }
+        let finished = Finished::new(&self.game.minesweeper, &self.name, Local::now().date_naive());
+        self.stats.record(finished);
+        self.save_stats();
+        if let Some(date) = self.daily {
+            if daily::is_board(&self.game.minesweeper, date) {
+                let finished = Finished::new(&self.game.minesweeper, &self.name, date);
+                if finished.real_win() {
+                    self.posted = Some(finished.clone());
+                    self.update_leaderboard(|leaderboard| leaderboard.record(finished));
                 }
             }
         }
-        
-        if self.minesweeper.left == 0 { self.minesweeper.won = true }
     }
 
-    fn clear_around(&mut self, row: usize, col: usize) {
-        let ref square = self.minesweeper.get(row, col);
-        let around = self.flags_around(row, col);
-        let i: u8 = square.content.clone().into();
-        if i != 0 && i == around {
-            if row > 0 {
-                self.reveal(row-1, col);
-                if col > 0 {
-                    self.reveal(row-1, col-1);
-                }
-                if col < self.minesweeper.cols - 1 {
-                    self.reveal(row-1, col+1);
-                }
+    /// Makes `change` to the leaderboard as it is on disk, and writes it back
+    fn update_leaderboard(&mut self, change: impl FnOnce(&mut Stats)) {
+        if let Some(path) = &self.leaderboard_file {
+            match Stats::load(path) {
+                Ok(leaderboard) => self.leaderboard = leaderboard,
+                Err(err) => eprintln!("{}: {}", path.display(), err),
             }
-            if row < self.minesweeper.rows - 1 {
-                self.reveal(row+1, col);
-                if col > 0 {
-                    self.reveal(row+1, col-1);
-                }
-                if col < self.minesweeper.cols - 1 {
-                    self.reveal(row+1, col+1);
-                }
+        }
+        change(&mut self.leaderboard);
+        if let Some(path) = &self.leaderboard_file {
+            if let Err(err) = self.leaderboard.save(path) {
+                eprintln!("{}: {}", path.display(), err);
             }
-            if col > 0 { self.reveal(row, col-1); }
-            if col < self.minesweeper.cols - 1 { self.reveal(row, col+1); }
         }
     }
 
-    fn reveal(&mut self, row: usize, col: usize) {
-        let square = self.minesweeper.get_mut(row, col);
-        // If flagged, don't click
-        if square.status == Status::Flagged || square.status == Status::Uncovered { return }
+    /// What the timer shows: the time left in a time attack, or the time taken on this board
+    pub fn clock(&self) -> u32 {
+        match &self.time_attack {
+            Some(time_attack) => time_attack.seconds_left(),
+            None => self.game.minesweeper.seconds(),
+        }
+    }
 
-        // Set status to uncovred, if not already set
-        square.status = Status::Uncovered;
-        // This is the recursive call for clearing blank spaces
-        // In this case, the content must be zero, and it must currently be covered
-        if square.content == Content::Zero { 
-            if row > 0 {
-                self.reveal(row-1, col);
-                if col > 0 {
-                    self.reveal(row-1, col-1);
-                }
-                if col < self.minesweeper.cols - 1 {
-                    self.reveal(row-1, col+1);
-                }
-            }
-            if row < self.minesweeper.rows - 1 {
-                self.reveal(row+1, col);
-                if col > 0 {
-                    self.reveal(row+1, col-1);
-                }
-                if col < self.minesweeper.cols - 1 {
-                    self.reveal(row+1, col+1);
-                }
+    fn save_stats(&self) {
+        if let Some(path) = &self.stats_file {
+            if let Err(err) = self.stats.save(path) {
+                eprintln!("{}: {}", path.display(), err);
             }
-            if col > 0 { self.reveal(row, col-1); }
-            if col < self.minesweeper.cols - 1 { self.reveal(row, col+1); }
+        }
+    }
 
-        // if it is a mine, lose
-        } else if square.content == Content::Mine {
-            self.lose();
+    /// Works the odds out again if the board has changed since they last were.
+    /// The solver counts one mine to a square, so there are none where mines can share one.
+    fn refresh_odds(&mut self) {
+        let ms = &self.game.minesweeper;
+        if self.odds_field != ms.field {
+            self.odds = if ms.per_square == 1 { solver::probabilities(&ms.field, ms.topology, ms.mines) } else { None };
+            self.odds_field = ms.field.clone();
         }
-        self.minesweeper.left -= 1;
     }
 
-    fn flags_around(&self, row: usize, col: usize) -> u8 {
-        let ref field = self.minesweeper.field;
-        let flag = Status::Flagged;
-        let mut count = 0;
-            if row > 0 {
-                if field[row-1][col].status == flag { count += 1}
-                if col > 0 {
-                    if field[row-1][col-1].status == flag { count += 1}
+    fn mouse_pressed(&mut self, action: Action, settings: MineSweeperViewSettings) -> Option<Action> {
+        let square = self.square_at_cursor(settings);
+        match action {
+            Action::Reveal => self.reveal_held = true,
+            Action::Flag => {
+                self.flag_held = true;
+                if let (false, Some((row, col))) = (self.reveal_held, square) {
+                    self.game.toggle_mark(row, col);
                 }
-                if col < self.minesweeper.cols - 1 {
-                    if field[row-1][col+1].status == flag { count += 1}
+            },
+            Action::Chord => self.chord_held = true,
+            _ => return self.act(action, square),
+        }
+        None
+    }
+
+    fn mouse_released(&mut self, action: Action, settings: MineSweeperViewSettings) {
+        let square = self.square_at_cursor(settings);
+        match action {
+            Action::Reveal | Action::Flag if self.reveal_held && self.flag_held => {
+                // releasing either button ends the chord; the other's release is ignored
+                self.reveal_held = false;
+                self.flag_held = false;
+                if let Some((row, col)) = square {
+                    self.game.chord(row, col);
                 }
-            }
-            if row < self.minesweeper.rows - 1 {
-                    if field[row+1][col].status == flag { count += 1}
-                if col > 0 {
-                    if field[row+1][col-1].status == flag { count += 1}
+            },
+            Action::Reveal if self.reveal_held => {
+                self.reveal_held = false;
+                self.act(Action::Reveal, square);
+            },
+            Action::Flag => self.flag_held = false,
+            Action::Chord if self.chord_held => {
+                self.chord_held = false;
+                self.act(Action::Chord, square);
+            },
+            _ => {},
+        }
+    }
+
+    fn key_pressed(&mut self, action: Action, settings: MineSweeperViewSettings) -> Option<Action> {
+        let (rows, cols) = (self.game.minesweeper.rows, self.game.minesweeper.cols);
+        // a new board may be smaller than the last
+        self.selected = (self.selected.0.min(rows - 1), self.selected.1.min(cols - 1));
+        let (row, col) = self.selected;
+        match action {
+            Action::CursorUp => self.selected.0 = row.saturating_sub(1),
+            Action::CursorDown => self.selected.0 = (row + 1).min(rows - 1),
+            Action::CursorLeft => self.selected.1 = col.saturating_sub(1),
+            Action::CursorRight => self.selected.1 = (col + 1).min(cols - 1),
+            _ => {
+                let square = if self.keyboard { Some(self.selected) } else { self.square_at_cursor(settings) };
+                return self.act(action, square);
+            },
+        }
+        self.keyboard = true;
+        None
+    }
+
+    /// Carries out `action` on `square`, if it needs one,
+    /// passing back the actions the caller has to handle
+    fn act(&mut self, action: Action, square: Option<(usize, usize)>) -> Option<Action> {
+        match (action, square) {
+            (Action::Reveal, Some((row, col))) => {
+                if self.game.minesweeper.get(row, col).status == Status::Uncovered {
+                    self.game.chord(row, col);
+                } else {
+                    self.game.reveal(row, col);
                 }
-                if col < self.minesweeper.cols - 1 {
-                    if field[row+1][col+1].status == flag { count += 1}
+            },
+            (Action::Flag, Some((row, col))) => self.game.toggle_mark(row, col),
+            (Action::Chord, Some((row, col))) => {
+                self.game.chord(row, col);
+            },
+            (Action::Reset, _) => self.reset(),
+            (Action::Pause, _) => self.paused = self.game.state() == Outcome::Playing,
+            (Action::BestTimes, _) => self.best_times = true,
+            (Action::Heatmap, _) => {
+                self.heatmap = !self.heatmap;
+                self.odds_field.clear();
+            },
+            // as with the odds, no hints where mines can share a square
+            (Action::Hint, _) if self.game.state() == Outcome::Playing && self.game.minesweeper.per_square == 1 => {
+                self.hint = solver::hint(&self.game.minesweeper.field, self.game.minesweeper.topology);
+            },
+            (Action::Undo, _) => {
+                let finished = self.game.state() != Outcome::Playing;
+                // taking back the move that ended the game takes the game back out of the statistics,
+                // since it will be recorded again when it ends
+                if self.game.undo() && finished && self.time_attack.is_none() {
+                    self.stats.games.pop();
+                    self.save_stats();
+                    // and a daily win back off the leaderboard
+                    if let Some(posted) = self.posted.take() {
+                        self.update_leaderboard(|leaderboard| {
+                            if let Some(i) = leaderboard.games.iter().rposition(|game| *game == posted) {
+                                leaderboard.games.remove(i);
+                            }
+                        });
+                    }
                 }
-            }
-            if col > 0 { if field[row][col-1].status == flag { count += 1 } }
-            if col < self.minesweeper.cols - 1 { if field[row][col+1].status == flag { count +=1 } }
-        count
-    }
-
-    fn mark(&mut self, row: usize, col: usize) {
-        let square = self.minesweeper.get_mut(row, col);
-        square.status = match square.status {
-            Status::Uncovered => Status::Uncovered,
-            Status::Unmarked => Status::Flagged,
-            Status::Flagged => Status::Questioned,
-            Status::Questioned => Status::Unmarked,
-        };
+            },
+            (Action::Redo, _) => {
+                self.game.redo();
+            },
+            (Action::Save, _) | (Action::Load, _) | (Action::Beginner, _) | (Action::Intermediate, _)
+            | (Action::Expert, _) | (Action::Chosen, _) => return Some(action),
+            _ => {},
+        }
+        None
+    }
 
+    /// Starts over: on the same board when playing the daily one,
+    /// and with the full time again in a time attack
+    fn reset(&mut self) {
+        match self.daily {
+            Some(date) => {
+                self.game.reset(daily::seed(date));
+                daily::open(&mut self.game);
+            },
+            None => self.game.reset(rand::random()),
+        }
+        if let Some(time_attack) = &mut self.time_attack {
+            time_attack.restart();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use piston::input::{ButtonArgs, ButtonState, Event, Input, MouseButton};
+
+    use minesweeper::Topology;
+
+    use super::*;
+
+    /// A controller on a 3x3 board with a mine in the top left corner, and the settings it is drawn with
+    fn controller() -> (MineSweeperController, MineSweeperViewSettings) {
+        let mut ms = MineSweeper::new(3, 3, 1, 0);
+        ms.place_mines(&[(0, 0)]);
+        let settings = MineSweeperViewSettings::new(ms.rows, ms.cols, Topology::Flat, 1.0);
+        (MineSweeperController::new(Game::new(ms), Bindings::default()), settings)
+    }
+
+    /// Moves the cursor to the middle of a square
+    fn point_at(controller: &mut MineSweeperController, settings: MineSweeperViewSettings, row: usize, col: usize) {
+        let top = settings.border_long * 2.0 + settings.smiley_side;
+        controller.cursor_pos = [
+            settings.border_long + (col as f64 + 0.5) * settings.square_side,
+            top + (row as f64 + 0.5) * settings.square_side,
+        ];
     }
 
-    fn lose(&mut self) {
-        self.minesweeper.lost = true;
+    fn mouse(controller: &mut MineSweeperController, settings: MineSweeperViewSettings, button: MouseButton, state: ButtonState) {
+        let args = ButtonArgs { state, button: Button::Mouse(button), scancode: None };
+        controller.event(settings, &Event::Input(Input::Button(args), None));
     }
 
-    fn reset(&mut self) {
-        self.minesweeper = MineSweeper::new();
+    #[test]
+    fn a_press_holds_its_square_and_a_chord_its_neighbours_too() {
+        let mut ms = MineSweeper::new(3, 3, 1, 0);
+        ms.field[0][1].status = Status::Flagged(1);
+        ms.field[2][2].status = Status::Uncovered;
+        ms.field[2][1].status = Status::Questioned;
+        assert_eq!(held_squares(&ms, (1, 1), false), vec![(1, 1)]);
+        assert_eq!(held_squares(&ms, (1, 1), true), vec![(1, 1), (0, 0), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)]);
+        assert!(held_squares(&ms, (2, 2), false).is_empty());
+        assert!(held_squares(&ms, (0, 1), false).is_empty());
+    }
+
+    #[test]
+    fn held_buttons_push_in_the_squares_under_the_cursor() {
+        let (mut controller, settings) = controller();
+        point_at(&mut controller, settings, 1, 1);
+        assert!(controller.pressed_squares(settings).is_empty());
+
+        mouse(&mut controller, settings, MouseButton::Left, ButtonState::Press);
+        assert_eq!(controller.pressed_squares(settings), vec![(1, 1)]);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Press);
+        assert!(controller.chording());
+        assert_eq!(controller.pressed_squares(settings).len(), 9);
+
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Release);
+        assert!(controller.pressed_squares(settings).is_empty());
+        assert!(!controller.pressing());
+    }
+
+    #[test]
+    fn releasing_either_of_both_buttons_chords() {
+        let (mut controller, settings) = controller();
+        controller.game.reveal(1, 1);
+        controller.game.toggle_mark(0, 0);
+        point_at(&mut controller, settings, 1, 1);
+
+        mouse(&mut controller, settings, MouseButton::Left, ButtonState::Press);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Press);
+        // the right press joins the chord instead of flagging
+        assert_eq!(controller.game.minesweeper.flags, 1);
+        mouse(&mut controller, settings, MouseButton::Left, ButtonState::Release);
+        assert_eq!(controller.game.state(), Outcome::Won);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Release);
+        assert_eq!(controller.game.minesweeper.get(0usize, 0usize).status, Status::Flagged(1));
+    }
+
+    #[test]
+    fn middle_click_chords_and_right_click_flags() {
+        let (mut controller, settings) = controller();
+        controller.game.reveal(1, 1);
+        point_at(&mut controller, settings, 0, 0);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Press);
+        assert_eq!(controller.game.minesweeper.get(0usize, 0usize).status, Status::Flagged(1));
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Release);
+
+        point_at(&mut controller, settings, 1, 1);
+        mouse(&mut controller, settings, MouseButton::Middle, ButtonState::Press);
+        assert_eq!(controller.game.state(), Outcome::Playing);
+        mouse(&mut controller, settings, MouseButton::Middle, ButtonState::Release);
+        assert_eq!(controller.game.state(), Outcome::Won);
+    }
+
+    #[test]
+    fn right_click_on_an_uncovered_square_does_nothing() {
+        let (mut controller, settings) = controller();
+        controller.game.reveal(1, 1);
+        let before = controller.game.minesweeper.clone();
+        point_at(&mut controller, settings, 1, 1);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Press);
+        mouse(&mut controller, settings, MouseButton::Right, ButtonState::Release);
+        assert_eq!(controller.game.minesweeper, before);
+        assert!(controller.game.undo());
+        assert!(!controller.game.undo());
     }
 }
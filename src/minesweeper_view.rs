@@ -1,14 +1,32 @@
-use graphics::{Context, Transformed, Image, rectangle::square};
+use graphics::{Context, Transformed, Image, Rectangle, rectangle::square};
 use opengl_graphics::{GlGraphics, Texture, TextureSettings};
 use std::path::Path;
 
 use crate::minesweeper_controller::MineSweeperController;
-use crate::minesweeper::{Content, Status};
+use crate::text;
+use chrono::NaiveDate;
+
+use minesweeper::{daily, Content, Difficulty, Status, Topology};
+use minesweeper::minesweeper::MAX_DISPLAY;
+use minesweeper::solver::Hint;
+use minesweeper::stats::Stats;
+
+/// Characters that fit across the best times screen, whatever the board's width
+const BEST_TIMES_WIDTH: usize = 30;
+/// Best times and bars of the time distribution listed on the best times screen
+const BEST_TIMES_SHOWN: usize = 5;
+const DISTRIBUTION_BARS: usize = 5;
+/// Players listed on the daily leaderboard
+const DAILY_SHOWN: usize = 10;
 
 struct Smiley {
     pub normal: Texture,
     pub x_eyes: Texture,
     pub sunglasses: Texture,
+    /// The "O" face shown while a square is being pressed
+    pub surprised: Texture,
+    /// The normal face pushed in, while the smiley itself is being clicked
+    pub pressed: Texture,
 }
 
 impl Smiley {
@@ -16,10 +34,14 @@ impl Smiley {
         let normal = Texture::from_path(Path::new("./assets/normalsmiley.gif"), texture_settings).expect("Could not find normal smiley");
         let x_eyes = Texture::from_path(Path::new("./assets/deadsmiley.gif"), texture_settings).expect("Could not find dead smiley");
         let sunglasses = Texture::from_path(Path::new("./assets/sunglassessmiley.gif"), texture_settings).expect("Could not find sunglasses smiley");
+        let surprised = Texture::from_path(Path::new("./assets/surprisedsmiley.gif"), texture_settings).expect("Could not find surprised smiley");
+        let pressed = Texture::from_path(Path::new("./assets/pressedsmiley.gif"), texture_settings).expect("Could not find pressed smiley");
         Smiley {
             normal,
             x_eyes,
             sunglasses,
+            surprised,
+            pressed,
         }
     }
 }
@@ -74,7 +96,7 @@ struct Textures {
     pub bombdeath: Texture,
     /// A misflagged bomb texture for when the user guesses incorrectly
     pub bombmisflagged: Texture,
-    /// Empty background space
+    /// Empty background space, filling the gaps beside the shifted rows of hex boards
     pub empty: Texture,
     /// All border pieces
     pub border: Border,
@@ -82,13 +104,15 @@ struct Textures {
     pub smiley: Smiley,
     /// All big red numbers
     pub numbers: [Texture; 10],
+    /// Big red minus sign for negative counts
+    pub minus: Texture,
 }
 
 impl Textures {
 
     /// Loads all texture assets
     pub fn new() -> Textures {
-        let ref texture_settings = TextureSettings::new();
+        let texture_settings = &TextureSettings::new();
         let blank = Texture::from_path(Path::new("./assets/blank.gif"), texture_settings).expect("Could not find image");
 
         // unsafe way to initialized an array incrementally
@@ -109,13 +133,13 @@ impl Textures {
 
             // dropping a `MaybeUninit` does nothing. Thus using raw pointer assignment
             // instead of `ptr::write` does not cause the old uninitialized value to be dropped.
-            for open in 0..SIZE {
-                opens[open] = MaybeUninit::new(Texture::from_path(Path::new(&format!("./assets/open{}.gif", open)), texture_settings).expect("Clould not find open image"));
+            for (open, slot) in opens.iter_mut().enumerate() {
+                *slot = MaybeUninit::new(Texture::from_path(Path::new(&format!("./assets/open{}.gif", open)), texture_settings).expect("Clould not find open image"));
             }
 
             // everything is initialized.
             // transmute the array to the initialized type
-            unsafe { mem::transmute::<_, [Texture; SIZE]>(opens) }
+            unsafe { mem::transmute::<[MaybeUninit<Texture>; SIZE], [Texture; SIZE]>(opens) }
 
         };
 
@@ -123,12 +147,14 @@ impl Textures {
             let mut numbers: [MaybeUninit<Texture>; 10] = unsafe {
                 MaybeUninit::uninit().assume_init()
             };
-            for number in 0..10 {
-                numbers[number] = MaybeUninit::new(Texture::from_path(Path::new(&format!("./assets/time{}.gif", number)), texture_settings).expect("Could not find number image"));
+            for (number, slot) in numbers.iter_mut().enumerate() {
+                *slot = MaybeUninit::new(Texture::from_path(Path::new(&format!("./assets/time{}.gif", number)), texture_settings).expect("Could not find number image"));
             }
-            unsafe { mem::transmute::<_, [Texture; 10]>(numbers) }
+            unsafe { mem::transmute::<[MaybeUninit<Texture>; 10], [Texture; 10]>(numbers) }
         };
 
+        let minus = Texture::from_path(Path::new("./assets/time-.gif"), texture_settings).expect("Could not find minus image");
+
         let mine = Texture::from_path(Path::new("./assets/bombrevealed.gif"), texture_settings).expect("Could not find bomb image");
         let flagged = Texture::from_path(Path::new("./assets/bombflagged.png"), texture_settings).expect("Could not find flagged");
         let questioned = Texture::from_path(Path::new("./assets/bombquestioned.gif"), texture_settings).expect("Could not find questioned");
@@ -152,58 +178,61 @@ impl Textures {
             border,
             smiley,
             numbers,
+            minus,
         }
     }
 
     /// Retrieves the correct square texture depending on the game state.
     pub fn by_index(&self, controller: &MineSweeperController, row: usize, col: usize) -> &Texture {
-        let square = &controller.minesweeper.get(row, col);
-        if !controller.minesweeper.lost {
+        let square = &controller.game.minesweeper.get(row, col);
+        if !controller.game.minesweeper.lost {
             match square.status {
                 Status::Uncovered => {
                     match square.content {
-                        Content::Zero => &self.opens[0],
-                        Content::One => &self.opens[1],
-                        Content::Two => &self.opens[2],
-                        Content::Three => &self.opens[3],
-                        Content::Four => &self.opens[4],
-                        Content::Five => &self.opens[5],
-                        Content::Six => &self.opens[6],
-                        Content::Seven => &self.opens[7],
-                        Content::Eight => &self.opens[8],
-                        Content::Mine => &self.mine,
+                        Content::Number(number) => self.open(number),
+                        // a mine set off while lives were left
+                        Content::Mines(_) => &self.bombdeath,
                     }
                 },
                 Status::Unmarked => &self.blank,
-                Status::Flagged => &self.flagged,
+                Status::Flagged(_) => &self.flagged,
                 Status::Questioned => &self.questioned,
             }
         } else {
             match square.status {
                 Status::Uncovered => {
                     match square.content {
-                        Content::Zero => &self.opens[0],
-                        Content::One => &self.opens[1],
-                        Content::Two => &self.opens[2],
-                        Content::Three => &self.opens[3],
-                        Content::Four => &self.opens[4],
-                        Content::Five => &self.opens[5],
-                        Content::Six => &self.opens[6],
-                        Content::Seven => &self.opens[7],
-                        Content::Eight => &self.opens[8],
-                        Content::Mine => &self.bombdeath,
+                        Content::Number(number) => self.open(number),
+                        Content::Mines(_) => &self.bombdeath,
                     }
                 },
-                Status::Unmarked => if square.content == Content::Mine { &self.mine } else { &self.blank },
-                Status::Flagged  => if square.content == Content::Mine { &self.flagged } else { &self.bombmisflagged },
-                Status::Questioned => if square.content == Content::Mine { &self.mine } else { &self.questioned },
+                Status::Unmarked => if square.content.is_mine() { &self.mine } else { &self.blank },
+                Status::Flagged(_) => if square.content.is_mine() { &self.flagged } else { &self.bombmisflagged },
+                Status::Questioned => if square.content.is_mine() { &self.mine } else { &self.questioned },
             }
         }
     }
 
-    pub fn smiley(&self, controller: &MineSweeperController) -> &Texture {
-        if controller.minesweeper.won { &self.smiley.sunglasses }
-        else if controller.minesweeper.lost { &self.smiley.x_eyes }
+    /// Retrieves the uncovered square showing `number`; numbers past eight
+    /// have no texture, so they get an empty one to be written over
+    fn open(&self, number: u8) -> &Texture {
+        self.opens.get(number as usize).unwrap_or(&self.opens[0])
+    }
+
+    /// Retrieves the three digits showing `value`, clamped to the range the display can show.
+    /// Negative values lead with a minus sign.
+    pub fn digits(&self, value: i64) -> [&Texture; 3] {
+        let value = value.clamp(-99, MAX_DISPLAY as i64);
+        let n = value.unsigned_abs() as usize;
+        let first = if value < 0 { &self.minus } else { &self.numbers[n / 100] };
+        [first, &self.numbers[n / 10 % 10], &self.numbers[n % 10]]
+    }
+
+    pub fn smiley(&self, controller: &MineSweeperController, settings: MineSweeperViewSettings) -> &Texture {
+        if controller.smiley_held && controller.over_smiley(settings) { &self.smiley.pressed }
+        else if controller.game.minesweeper.won { &self.smiley.sunglasses }
+        else if controller.game.minesweeper.lost { &self.smiley.x_eyes }
+        else if controller.pressing() { &self.smiley.surprised }
         else { &self.smiley.normal }
     }
 
@@ -215,6 +244,11 @@ pub struct MineSweeperViewSettings {
     pub square_side: f64,
     pub rows: usize,
     pub cols: usize,
+    /// How the board is laid out; odd rows of hex boards are drawn shifted half a square right
+    pub topology: Topology,
+    /// Size of the board inside the border
+    pub board_width: f64,
+    pub board_height: f64,
     pub border_long: f64,
     pub border_short: f64,
     pub smiley_side: f64,
@@ -225,19 +259,25 @@ pub struct MineSweeperViewSettings {
 }
 
 impl MineSweeperViewSettings {
-    pub fn new(rows: usize, cols: usize, scale: f64) -> Self {
+    pub fn new(rows: usize, cols: usize, topology: Topology, scale: f64) -> Self {
         let square_side = 16.0 * scale;
+        let shift = if topology == Topology::Hex && rows > 1 { square_side / 2.0 } else { 0.0 };
+        let board_width = cols as f64 * square_side + shift;
+        let board_height = rows as f64 * square_side;
         let border_long = 10.0 * scale;
         let border_short = 4.0 * scale;
         let smiley_side = 28.0 * scale;
         let time_height = 23.0 * scale;
         let time_width = 13.0 * scale;
-        let scr_width = border_long * 2.0 + cols as f64 * square_side;
-        let scr_height = border_long * 3.0 + rows as f64 * square_side + smiley_side;
+        let scr_width = border_long * 2.0 + board_width;
+        let scr_height = border_long * 3.0 + board_height + smiley_side;
         MineSweeperViewSettings {
             square_side,
             rows,
             cols,
+            topology,
+            board_width,
+            board_height,
             border_long,
             border_short,
             smiley_side,
@@ -247,6 +287,19 @@ impl MineSweeperViewSettings {
             scr_height,
         }
     }
+
+    /// How far a row is shifted right of the board's left edge
+    pub fn row_shift(&self, row: usize) -> f64 {
+        if self.topology == Topology::Hex && row % 2 == 1 { self.square_side / 2.0 } else { 0.0 }
+    }
+
+    /// The top left corner of the square at `(row, col)` in the window
+    pub fn square_pos(&self, row: usize, col: usize) -> [f64; 2] {
+        [
+            self.border_long + self.row_shift(row) + col as f64 * self.square_side,
+            self.border_long * 2.0 + self.smiley_side + row as f64 * self.square_side,
+        ]
+    }
 }
 
 /// Struct in charge of drawing to the screen.
@@ -269,10 +322,10 @@ impl MineSweeperView {
         g: &mut GlGraphics
     )
     {
-        let ref settings = self.settings;
+        let settings = &self.settings;
         let mid_divider = settings.border_long + settings.smiley_side;
-        let bot_divider = settings.border_long * 2.0 + settings.smiley_side + settings.rows as f64 * settings.square_side;
-        let far_right = settings.border_long + settings.cols as f64 * settings.square_side;
+        let bot_divider = settings.border_long * 2.0 + settings.smiley_side + settings.board_height;
+        let far_right = settings.border_long + settings.board_width;
 
         // draw corner and join pieces
         let side = settings.border_long;
@@ -308,11 +361,12 @@ impl MineSweeperView {
                    c.transform.trans(far_right, mid_divider),
                    g);
 
-        // draw horizontal borders - 124 total
+        // draw horizontal borders
         let width = settings.border_short;
         let height = settings.border_long;
         let image = Image::new().rect([0.0, 0.0, width, height]);
-        for i in 0..124 {
+        let amount = (settings.board_width / settings.border_short).ceil() as usize;
+        for i in 0..amount {
             image.draw(&self.textures.border.topbottom,
                        &c.draw_state,
                        c.transform.trans(height + (i as f64 * width), 0.0),
@@ -343,7 +397,7 @@ impl MineSweeperView {
                        g);
         }
         let below_join = settings.border_long * 2.0 + settings.smiley_side;
-        let amount = (settings.rows as f64 * settings.square_side / settings.border_short) as usize;
+        let amount = (settings.board_height / settings.border_short).ceil() as usize;
         for i in 0..amount {
             image.draw(&self.textures.border.leftright,
                        &c.draw_state,
@@ -355,14 +409,61 @@ impl MineSweeperView {
                        g);
         }
 
-        let width = settings.time_width;
-        let height = settings.time_height;
-        let image = Image::new().rect([0.0, 0.0, width, height]);
+        // draw mine counter on the left and timer on the right
+        let image = Image::new().rect([0.0, 0.0, settings.time_width, settings.time_height]);
+        let top = settings.border_long + (settings.smiley_side - settings.time_height) / 2.0;
+        let counter_left = settings.border_long + settings.border_short;
+        let timer_left = far_right - settings.border_short - 3.0 * settings.time_width;
+        let counter = self.textures.digits(controller.game.minesweeper.mines_left());
+        let timer = self.textures.digits(controller.clock() as i64);
+        for (i, (mine_digit, time_digit)) in counter.iter().zip(timer.iter()).enumerate() {
+            let digit_offset = i as f64 * settings.time_width;
+            image.draw(*mine_digit,
+                       &c.draw_state,
+                       c.transform.trans(counter_left + digit_offset, top),
+                       g);
+            image.draw(*time_digit,
+                       &c.draw_state,
+                       c.transform.trans(timer_left + digit_offset, top),
+                       g);
+        }
 
+        // in lives mode, a heart and the lives left sit between the mine counter and the smiley
+        let ms = &controller.game.minesweeper;
+        if ms.lives > 1 {
+            let lives = format!("{}{}", text::HEART, ms.lives.saturating_sub(ms.hits));
+            let counter_right = counter_left + 3.0 * settings.time_width;
+            let gap = settings.scr_width / 2.0 - settings.smiley_side / 2.0 - counter_right;
+            let dot = (settings.square_side / 16.0).min(gap / (lives.chars().count() as f64 * text::ADVANCE + 1.0));
+            let width = (lives.chars().count() as f64 * text::ADVANCE - 1.0) * dot;
+            let height = (text::LINE - 2.0) * dot;
+            text::draw(&lives,
+                       [counter_right + (gap - width) / 2.0, settings.border_long + (settings.smiley_side - height) / 2.0],
+                       dot,
+                       [0.8, 0.0, 0.0, 1.0],
+                       c,
+                       g);
+        }
+
+        // in a time attack, the boards cleared sit between the smiley and the timer
+        if let Some(time_attack) = &controller.time_attack {
+            let score = time_attack.cleared.to_string();
+            let smiley_right = settings.scr_width / 2.0 + settings.smiley_side / 2.0;
+            let gap = timer_left - smiley_right;
+            let dot = (settings.square_side / 16.0).min(gap / (score.chars().count() as f64 * text::ADVANCE + 1.0));
+            let width = (score.chars().count() as f64 * text::ADVANCE - 1.0) * dot;
+            let height = (text::LINE - 2.0) * dot;
+            text::draw(&score,
+                       [smiley_right + (gap - width) / 2.0, settings.border_long + (settings.smiley_side - height) / 2.0],
+                       dot,
+                       [0.0, 0.0, 0.0, 1.0],
+                       c,
+                       g);
+        }
 
         let side = settings.smiley_side;
         let image = Image::new().rect(square(0.0, 0.0, side));
-        let texture = self.textures.smiley(controller);
+        let texture = self.textures.smiley(controller, self.settings);
         image.draw(texture,
                    &c.draw_state,
                    c.transform.trans(settings.scr_width/2.0 - settings.smiley_side/2.0, settings.border_long),
@@ -374,17 +475,208 @@ impl MineSweeperView {
             self.settings.border_long,
             self.settings.border_long * 2.0 + self.settings.smiley_side,
         ];
+        // fill the half square each row of a hex board leaves at one end
+        if settings.board_width > settings.cols as f64 * settings.square_side {
+            let gap = Image::new().rect([0.0, 0.0, settings.square_side / 2.0, settings.square_side]);
+            for i in 0..settings.rows {
+                let left = if settings.row_shift(i) > 0.0 { 0.0 } else { settings.board_width - settings.square_side / 2.0 };
+                gap.draw(&self.textures.empty,
+                         &c.draw_state,
+                         c.transform.trans(offset[0] + left, offset[1] + i as f64 * settings.square_side),
+                         g);
+            }
+        }
+        // squares under a held button are drawn pushed in, like an empty uncovered square
+        let pressed = controller.pressed_squares(self.settings);
         for i in 0..settings.rows{
             for j in 0..settings.cols {
-                let pos = [
-                    (j as f64 * settings.square_side) + offset[0],
-                    (i as f64 * settings.square_side) + offset[1],
-                ];
-                image.draw(self.textures.by_index(controller, i, j),
+                let pos = settings.square_pos(i, j);
+                // the board is hidden while paused
+                let texture = if controller.paused || controller.best_times {
+                    &self.textures.blank
+                } else if pressed.contains(&(i, j)) {
+                    &self.textures.opens[0]
+                } else {
+                    self.textures.by_index(controller, i, j)
+                };
+                image.draw(texture,
                            &c.draw_state,
                            c.transform.trans(pos[0], pos[1]),
                            g);
+                if controller.paused || controller.best_times || pressed.contains(&(i, j)) { continue }
+                if let Some((count, middle)) = count_over(controller, i, j) {
+                    // dots the size of the textures' pixels
+                    let dot = settings.square_side / 16.0;
+                    let width = (count.len() as f64 * text::ADVANCE - 1.0) * dot;
+                    let height = (text::LINE - 2.0) * dot;
+                    let (left, top) = if middle {
+                        ((settings.square_side - width) / 2.0, (settings.square_side - height) / 2.0)
+                    } else {
+                        (settings.square_side - width - dot * 2.0, settings.square_side - height - dot * 2.0)
+                    };
+                    text::draw(&count, [pos[0] + left, pos[1] + top], dot, [0.5, 0.0, 0.0, 1.0], c, g);
+                }
             }
         }
+
+        // tint covered squares from green to red by their chance of holding a mine,
+        // and show the chance as a percentage on the square under the mouse
+        if let (true, false, Some(odds)) = (controller.heatmap, controller.paused, &controller.odds) {
+            let side = settings.square_side;
+            for (i, row) in odds.iter().enumerate() {
+                for (j, p) in row.iter().enumerate() {
+                    if let Some(p) = *p {
+                        if controller.game.minesweeper.get(i, j).status == Status::Uncovered { continue }
+                        let [x, y] = settings.square_pos(i, j);
+                        Rectangle::new([p as f32, 1.0 - p as f32, 0.0, 0.45])
+                            .draw([x, y, side, side],
+                                  &c.draw_state,
+                                  c.transform,
+                                  g);
+                    }
+                }
+            }
+            if let Some((i, j)) = controller.square_at_cursor(self.settings) {
+                if let Some(p) = odds[i][j] {
+                    let width = side / 3.0;
+                    let height = width * settings.time_height / settings.time_width;
+                    let [left, top] = settings.square_pos(i, j);
+                    let top = top + (side - height) / 2.0;
+                    let image = Image::new().rect([0.0, 0.0, width, height]);
+                    let percent = (p * 100.0).round() as i64;
+                    for (k, digit) in self.textures.digits(percent).iter().enumerate() {
+                        image.draw(*digit,
+                                   &c.draw_state,
+                                   c.transform.trans(left + k as f64 * width, top),
+                                   g);
+                    }
+                }
+            }
+        }
+
+        // tint the hinted square green if it is clear, red if it is a mine
+        let hint = match controller.hint {
+            Some(Hint::Safe(pos)) => Some(([0.0, 0.8, 0.0, 0.5], pos)),
+            Some(Hint::Mine(pos)) => Some(([0.9, 0.0, 0.0, 0.5], pos)),
+            None => None,
+        };
+        if let Some((colour, (row, col))) = hint {
+            let side = settings.square_side;
+            let [x, y] = settings.square_pos(row, col);
+            Rectangle::new(colour)
+                .draw([x, y, side, side],
+                      &c.draw_state,
+                      c.transform,
+                      g);
+        }
+
+        // outline the square picked with the keyboard, unless a new board left it behind
+        let (row, col) = controller.selected;
+        if controller.keyboard && !controller.paused && row < settings.rows && col < settings.cols {
+            let side = settings.square_side;
+            let [x, y] = settings.square_pos(row, col);
+            Rectangle::new_border([1.0, 0.0, 0.0, 1.0], side / 16.0)
+                .draw([x, y, side, side],
+                      &c.draw_state,
+                      c.transform,
+                      g);
+        }
+
+        if controller.best_times {
+            let ms = &controller.game.minesweeper;
+            let difficulty = Difficulty::of(ms.rows, ms.cols, ms.mines);
+            let lines = match controller.daily {
+                Some(date) => daily_ranking(&controller.leaderboard, date, difficulty),
+                None => best_times(&controller.stats, difficulty, ms.topology, ms.per_square),
+            };
+            let (width, height) = (settings.board_width, settings.board_height);
+            Rectangle::new([0.75, 0.75, 0.75, 1.0])
+                .draw([offset[0], offset[1], width, height], &c.draw_state, c.transform, g);
+            // shrink the dots on boards too small to fit every line
+            let dot = (width / (BEST_TIMES_WIDTH as f64 * text::ADVANCE + 2.0))
+                .min(height / ((lines.len() as f64 + 1.0) * text::LINE))
+                .max(1.0);
+            for (i, line) in lines.iter().enumerate() {
+                text::draw(line,
+                           [offset[0] + dot * 2.0, offset[1] + dot * (2.0 + i as f64 * text::LINE)],
+                           dot,
+                           [0.0, 0.0, 0.0, 1.0],
+                           c,
+                           g);
+            }
+        }
+    }
+}
+
+/// A count the square textures have no room for, and whether it goes in the middle of
+/// the square rather than its corner: numbers past eight, and how many flags or mines
+/// share a square when there are several
+fn count_over(controller: &MineSweeperController, row: usize, col: usize) -> Option<(String, bool)> {
+    let ms = &controller.game.minesweeper;
+    let square = ms.get(row, col);
+    match (&square.status, &square.content) {
+        (Status::Uncovered, &Content::Number(number)) if number > 8 => Some((number.to_string(), true)),
+        (&Status::Flagged(flags), _) if flags > 1 => Some((flags.to_string(), false)),
+        // losing shows every mine, along with how many share each square
+        (_, &Content::Mines(mines)) if mines > 1 && ms.lost => Some((mines.to_string(), false)),
+        _ => None,
+    }
+}
+
+/// The lines of the best times screen for `difficulty` on boards joined up by `topology`,
+/// with up to `per_square` mines on a square
+fn best_times(stats: &Stats, difficulty: Difficulty, topology: Topology, per_square: u8) -> Vec<String> {
+    let summary = stats.summary(difficulty, topology, per_square);
+    let mut title = match topology {
+        Topology::Flat => format!("BEST TIMES {}", difficulty),
+        _ => format!("BEST TIMES {} {}", difficulty, topology),
+    };
+    if per_square > 1 {
+        title += &format!(" X{}", per_square);
+    }
+    let mut lines = vec![
+        title,
+        format!("PLAYED {} WON {} ({:.0}%)", summary.played, summary.won, summary.win_rate() * 100.0),
+        format!("STREAK {} LONGEST {}", summary.streak, summary.longest_streak),
+    ];
+    if summary.hits > 0 {
+        lines.push(format!("MINES HIT {}", summary.hits));
+    }
+    lines.push(String::new());
+    if summary.best.is_empty() {
+        lines.push("NO WINS YET".to_string());
+    }
+    for (i, game) in summary.best.iter().take(BEST_TIMES_SHOWN).enumerate() {
+        let name: String = game.name.chars().take(10).collect();
+        lines.push(format!("{} {:>7.2} {}", i + 1, game.time, name));
+        let score = game.score();
+        lines.push(format!("  {} {:.2} 3BV/S", game.date, score.bbbv_per_second()));
+    }
+
+    let distribution = stats.distribution(difficulty, topology, per_square, DISTRIBUTION_BARS);
+    if !distribution.is_empty() {
+        lines.push(String::new());
+        lines.push("TIMES".to_string());
+        let most = distribution.iter().map(|&(_, count)| count).max().unwrap_or(1);
+        let longest = BEST_TIMES_WIDTH - 8;
+        for (start, count) in distribution {
+            let bar = (count * longest).div_ceil(most);
+            lines.push(format!("{:>5.0}S {}", start, "#".repeat(bar)));
+        }
+    }
+    lines
+}
+
+/// The lines of the best times screen while playing the daily board: the day's leaderboard
+fn daily_ranking(leaderboard: &Stats, date: NaiveDate, difficulty: Difficulty) -> Vec<String> {
+    let ranking = daily::ranking(leaderboard, date, difficulty);
+    let mut lines = vec![format!("DAILY {} {}", difficulty, date), String::new()];
+    if ranking.is_empty() {
+        lines.push("NO WINS YET".to_string());
+    }
+    for (i, game) in ranking.iter().take(DAILY_SHOWN).enumerate() {
+        let name: String = game.name.chars().take(10).collect();
+        lines.push(format!("{:>2} {:>7.2} {}", i + 1, game.time, name));
     }
+    lines
 }
@@ -0,0 +1,363 @@
+//! Recording every move of a game and playing it back.
+//!
+//! A replay holds the board as it was when recording began, followed by each
+//! action with the time it was made. The mine layout is stored with the reveal
+//! that placed it, so playback never depends on how boards are generated.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::minesweeper::MineSweeper;
+use crate::save::{self, SaveError, VERSION};
+use crate::solver::Pos;
+
+/// A move the player made
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Reveal { row: usize, col: usize },
+    Mark { row: usize, col: usize },
+    Chord { row: usize, col: usize },
+    Undo,
+    Redo,
+    /// Started over on a board of the same size generated from `seed`
+    Reset { seed: u64 },
+    /// Switched to a different board, such as a new difficulty or a loaded game
+    Replace { board: Box<MineSweeper> },
+}
+
+/// An action and when it happened
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// Seconds since recording began
+    pub time: f64,
+    pub action: Action,
+    /// The mines placed by this action, if it was the first reveal of a board
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Vec<Pos>>,
+}
+
+/// A recorded game
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    /// The board when recording began
+    pub start: MineSweeper,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Serialize)]
+struct ReplayFile<'a> {
+    version: u64,
+    #[serde(flatten)]
+    replay: &'a Replay,
+}
+
+impl Replay {
+    /// Serializes a replay, tagged with the save format version
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ReplayFile { version: VERSION, replay: self })
+            .expect("replays always serialize")
+    }
+
+    /// Deserializes a replay and checks that it can be played back
+    pub fn from_json(s: &str) -> Result<Self, SaveError> {
+        let replay: Replay = serde_json::from_value(save::versioned(s)?)?;
+        replay.validate().map_err(SaveError::Corrupt)?;
+        Ok(replay)
+    }
+
+    /// Checks every board the replay plays on, and that each step stays
+    /// on the board in play when it is applied
+    fn validate(&self) -> Result<(), String> {
+        save::validate(&self.start)?;
+        let mut board = &self.start;
+        for (i, step) in self.steps.iter().enumerate() {
+            let (rows, cols) = (board.rows, board.cols);
+            let off_board = |&(row, col): &Pos| row >= rows || col >= cols;
+            if let Some(layout) = &step.layout {
+                if layout.iter().any(off_board) {
+                    return Err(format!("step {} places mines off the {} by {} board", i, rows, cols));
+                }
+                if layout.len() != board.mines {
+                    return Err(format!("step {} places {} mines on a board with {}", i, layout.len(), board.mines));
+                }
+                let stacked = layout.iter().any(|pos| layout.iter().filter(|&other| other == pos).count() > board.per_square as usize);
+                if stacked {
+                    return Err(format!("step {} stacks more than {} mines on a square", i, board.per_square));
+                }
+            }
+            match &step.action {
+                &Action::Reveal { row, col } | &Action::Mark { row, col } | &Action::Chord { row, col }
+                    if off_board(&(row, col)) => {
+                    return Err(format!("step {} is off the {} by {} board", i, rows, cols));
+                },
+                Action::Replace { board: next } => {
+                    save::validate(next).map_err(|reason| format!("step {} switches to a bad board: {}", i, reason))?;
+                    board = next;
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a replay to `path`
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// Reads a replay back from `path`
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// Collects the actions applied to a game as they happen
+pub struct Recorder {
+    pub replay: Replay,
+    clock: f64,
+}
+
+impl Recorder {
+    /// Starts recording from `start`
+    pub fn new(start: &MineSweeper) -> Self {
+        Recorder {
+            replay: Replay { start: start.clone(), steps: vec![] },
+            clock: 0.0,
+        }
+    }
+
+    /// Advances the recording clock by `dt` seconds
+    pub fn tick(&mut self, dt: f64) {
+        self.clock += dt;
+    }
+
+    pub fn record(&mut self, action: Action, layout: Option<Vec<Pos>>) {
+        self.replay.steps.push(Step { time: self.clock, action, layout });
+    }
+}
+
+/// Plays a replay back onto a game, either in time with the recording or step by step
+pub struct Player {
+    pub replay: Replay,
+    /// How much faster than the recording to play
+    pub speed: f64,
+    pub paused: bool,
+    next: usize,
+    clock: f64,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        Player { replay, speed: 1.0, paused: false, next: 0, clock: 0.0 }
+    }
+
+    /// A game as it was when recording began
+    pub fn start(&self) -> Game {
+        Game::new(self.replay.start.clone())
+    }
+
+    /// Rewinds to the beginning, putting `game` back in its starting state
+    pub fn restart(&mut self, game: &mut Game) {
+        *game = self.start();
+        self.next = 0;
+        self.clock = 0.0;
+    }
+
+    /// Whether every step has been played
+    pub fn finished(&self) -> bool {
+        self.next >= self.replay.steps.len()
+    }
+
+    /// Seconds into the recording
+    pub fn clock(&self) -> f64 {
+        self.clock
+    }
+
+    /// Moves `dt` seconds of real time forward, scaled by `speed`,
+    /// applying every step that falls inside it
+    pub fn advance(&mut self, game: &mut Game, dt: f64) {
+        if self.paused { return }
+        let dt = dt * self.speed;
+        self.clock += dt;
+        game.tick(dt);
+        while let Some(step) = self.replay.steps.get(self.next) {
+            if step.time > self.clock { break }
+            apply(game, step);
+            self.next += 1;
+        }
+    }
+
+    /// Applies the next step right away, jumping the clock to it
+    pub fn step(&mut self, game: &mut Game) {
+        if let Some(step) = self.replay.steps.get(self.next) {
+            game.tick((step.time - self.clock).max(0.0));
+            self.clock = self.clock.max(step.time);
+            apply(game, step);
+            self.next += 1;
+        }
+    }
+}
+
+/// Applies a recorded step, placing its recorded mines rather than generating new ones
+fn apply(game: &mut Game, step: &Step) {
+    if let Some(layout) = &step.layout {
+        if !game.minesweeper.generated {
+            game.minesweeper.place_mines(layout);
+        }
+    }
+    match &step.action {
+        &Action::Reveal { row, col } => {
+            game.reveal(row, col);
+        },
+        &Action::Mark { row, col } => game.toggle_mark(row, col),
+        &Action::Chord { row, col } => {
+            game.chord(row, col);
+        },
+        Action::Undo => {
+            game.undo();
+        },
+        Action::Redo => {
+            game.redo();
+        },
+        &Action::Reset { seed } => game.reset(seed),
+        Action::Replace { board } => game.replace(*board.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use crate::minesweeper::{Content, Difficulty, Generation, Status};
+
+    /// Plays a recorded beginner game: a few flags, one undone and redone, reveals and a chord,
+    /// then a reset and a loss on the second board
+    fn recorded_game() -> Game {
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Beginner, 21);
+        // time-limited generation isn't reproducible from the seed, so the layout must be
+        ms.generation = Generation::no_guess();
+        let mut game = Game::new(ms);
+        game.recorder = Some(Recorder::new(&game.minesweeper));
+
+        game.tick(0.5);
+        game.reveal(4, 4);
+        let mines = game.minesweeper.layout();
+        for &(row, col) in mines.iter().take(3) {
+            game.tick(0.25);
+            game.toggle_mark(row, col);
+        }
+        game.undo();
+        game.redo();
+        for row in 0..9 {
+            for col in 0..9 {
+                if game.minesweeper.get(row, col).status == Status::Uncovered {
+                    game.tick(0.1);
+                    game.chord(row, col);
+                }
+            }
+        }
+        game.tick(1.0);
+        game.reset(99);
+        game.reveal(0, 0);
+        let (row, col) = game.minesweeper.layout()[0];
+        game.tick(0.3);
+        game.reveal(row, col);
+        game
+    }
+
+    fn play_to_end(player: &mut Player) -> Game {
+        let mut game = player.start();
+        while !player.finished() {
+            player.step(&mut game);
+        }
+        game
+    }
+
+    #[test]
+    fn replay_reproduces_final_board() {
+        let game = recorded_game();
+        let replay = game.recorder.as_ref().unwrap().replay.clone();
+        let replay = Replay::from_json(&replay.to_json()).unwrap();
+
+        let played = play_to_end(&mut Player::new(replay));
+        assert_eq!(played.state(), Outcome::Lost);
+        assert_eq!(played.minesweeper.field, game.minesweeper.field);
+        assert_eq!(played.minesweeper.left, game.minesweeper.left);
+        assert_eq!(played.minesweeper.seed, game.minesweeper.seed);
+    }
+
+    #[test]
+    fn layout_is_recorded_with_first_reveal() {
+        let game = recorded_game();
+        let steps = &game.recorder.as_ref().unwrap().replay.steps;
+        let with_layout: Vec<_> = steps.iter().filter(|step| step.layout.is_some()).collect();
+        assert_eq!(with_layout.len(), 2);
+        assert_eq!(with_layout[0].action, Action::Reveal { row: 4, col: 4 });
+        assert_eq!(with_layout[1].action, Action::Reveal { row: 0, col: 0 });
+    }
+
+    #[test]
+    fn advance_follows_recorded_times() {
+        let game = recorded_game();
+        let mut player = Player::new(game.recorder.unwrap().replay);
+        let mut played = player.start();
+
+        player.advance(&mut played, 0.4);
+        assert!(!played.minesweeper.generated);
+        player.advance(&mut played, 0.2);
+        assert_eq!(played.minesweeper.get(4usize, 4usize).status, Status::Uncovered);
+
+        player.paused = true;
+        player.advance(&mut played, 100.0);
+        assert!(!player.finished());
+
+        player.paused = false;
+        player.speed = 4.0;
+        player.advance(&mut played, 100.0);
+        assert!(player.finished());
+        assert_eq!(played.state(), Outcome::Lost);
+    }
+
+    #[test]
+    fn rejects_steps_that_cannot_be_played() {
+        let replay = recorded_game().recorder.unwrap().replay;
+        let corrupt = |edit: &dyn Fn(&mut Replay)| {
+            let mut replay = replay.clone();
+            edit(&mut replay);
+            matches!(Replay::from_json(&replay.to_json()), Err(SaveError::Corrupt(_)))
+        };
+
+        assert!(corrupt(&|replay| replay.steps[1].action = Action::Mark { row: 9, col: 0 }));
+        assert!(corrupt(&|replay| replay.steps[0].layout.as_mut().unwrap()[0] = (0, 9)));
+        assert!(corrupt(&|replay| replay.steps[0].layout = Some(vec![(0, 0); 300])));
+        assert!(corrupt(&|replay| replay.steps[0].layout.as_mut().unwrap().push((0, 0))));
+        assert!(corrupt(&|replay| {
+            let layout = replay.steps[0].layout.as_mut().unwrap();
+            layout[1] = layout[0];
+        }));
+        let mut bad_board = MineSweeper::with_difficulty(Difficulty::Beginner, 1);
+        bad_board.left += 1;
+        assert!(corrupt(&|replay| replay.steps.insert(1, Step { time: 0.5, action: Action::Replace { board: Box::new(bad_board.clone()) }, layout: None })));
+
+        // a move is checked against the board in play when it is made
+        let bigger = MineSweeper::with_difficulty(Difficulty::Expert, 1);
+        assert!(!corrupt(&|replay| {
+            replay.steps.push(Step { time: 10.0, action: Action::Replace { board: Box::new(bigger.clone()) }, layout: None });
+            replay.steps.push(Step { time: 10.5, action: Action::Mark { row: 15, col: 29 }, layout: None });
+        }));
+    }
+
+    #[test]
+    fn restart_rewinds() {
+        let game = recorded_game();
+        let mut player = Player::new(game.recorder.unwrap().replay);
+        let mut played = play_to_end(&mut player);
+        player.restart(&mut played);
+        assert!(!player.finished());
+        assert!(played.minesweeper.field.iter().flatten().all(|square| square.content == Content::Number(0)));
+    }
+}
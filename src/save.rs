@@ -0,0 +1,344 @@
+//! Saving a game in progress to disk and resuming it later.
+//!
+//! Games are stored as JSON tagged with a format version. Loading checks the
+//! version and that the board is consistent with itself, so a damaged or
+//! hand-edited file is refused rather than played. Replays use the same checks.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::minesweeper::{Content, MineSweeper, Status, MAX_PER_SQUARE};
+
+/// Version of the save format written by this build.
+/// Version 2 stores squares' mines and flags as counts, so several can share a square.
+pub const VERSION: u64 = 2;
+
+/// Oldest version still read, whose squares are brought up to date as they load
+const OLDEST_VERSION: u64 = 1;
+
+/// Why a saved game or replay couldn't be written or read back
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The file isn't valid JSON or is missing parts of the game
+    Format(serde_json::Error),
+    /// The file was written by an incompatible version
+    Version(Option<u64>),
+    /// The file parsed, but describes an impossible board
+    Corrupt(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "could not access file: {}", err),
+            SaveError::Format(err) => write!(f, "file is malformed: {}", err),
+            SaveError::Version(Some(version)) => {
+                write!(f, "file has version {}, but only versions {} to {} are supported", version, OLDEST_VERSION, VERSION)
+            },
+            SaveError::Version(None) => write!(f, "file has no version"),
+            SaveError::Corrupt(reason) => write!(f, "file is corrupted: {}", reason),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(err) => Some(err),
+            SaveError::Format(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self { SaveError::Io(err) }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self { SaveError::Format(err) }
+}
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u64,
+    game: &'a MineSweeper,
+}
+
+/// Serializes a game to the save format
+pub fn to_string(ms: &MineSweeper) -> String {
+    serde_json::to_string(&SaveFile { version: VERSION, game: ms })
+        .expect("games always serialize")
+}
+
+/// Deserializes and checks a game in the save format
+pub fn from_str(s: &str) -> Result<MineSweeper, SaveError> {
+    let file = versioned(s)?;
+    let game = file.get("game").cloned().unwrap_or(Value::Null);
+    let ms: MineSweeper = serde_json::from_value(game)?;
+    validate(&ms).map_err(SaveError::Corrupt)?;
+    Ok(ms)
+}
+
+/// Parses JSON whose `version` field must be a format this build reads,
+/// upgrading older formats to the current one
+pub(crate) fn versioned(s: &str) -> Result<Value, SaveError> {
+    let mut file: Value = serde_json::from_str(s)?;
+    match file.get("version").and_then(Value::as_u64) {
+        Some(VERSION) => {},
+        Some(1) => upgrade_squares(&mut file),
+        version => return Err(SaveError::Version(version)),
+    }
+    Ok(file)
+}
+
+/// Rewrites every square in a version 1 file, wherever it is, in the version 2 form:
+/// version 1 named numbers from `Zero` to `Eight`, a single `Mine` and a single `Flagged`
+fn upgrade_squares(value: &mut Value) {
+    match value {
+        Value::Object(square) if square.contains_key("content") && square.contains_key("status") => {
+            const NUMBERS: [&str; 9] = ["Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight"];
+            let content = match square["content"].as_str() {
+                Some("Mine") => Some(json!({ "Mines": 1 })),
+                Some(name) => NUMBERS.iter().position(|&number| number == name).map(|number| json!({ "Number": number })),
+                None => None,
+            };
+            if let Some(content) = content {
+                square["content"] = content;
+            }
+            if square["status"] == "Flagged" {
+                square["status"] = json!({ "Flagged": 1 });
+            }
+        },
+        Value::Object(object) => object.values_mut().for_each(upgrade_squares),
+        Value::Array(array) => array.iter_mut().for_each(upgrade_squares),
+        _ => {},
+    }
+}
+
+/// Writes a game to `path`
+pub fn save(ms: &MineSweeper, path: &Path) -> Result<(), SaveError> {
+    fs::write(path, to_string(ms))?;
+    Ok(())
+}
+
+/// Reads a game back from `path`
+pub fn load(path: &Path) -> Result<MineSweeper, SaveError> {
+    from_str(&fs::read_to_string(path)?)
+}
+
+/// Checks that a loaded board could have come from a real game
+pub(crate) fn validate(ms: &MineSweeper) -> Result<(), String> {
+    if ms.rows == 0 || ms.cols == 0 {
+        return Err("board has no squares".to_string());
+    }
+    if ms.field.len() != ms.rows || ms.field.iter().any(|row| row.len() != ms.cols) {
+        return Err(format!("board is not {} by {}", ms.rows, ms.cols));
+    }
+    if ms.mines >= ms.rows * ms.cols {
+        return Err(format!("{} mines don't fit on the board", ms.mines));
+    }
+    if ms.per_square == 0 || ms.per_square > MAX_PER_SQUARE {
+        return Err(format!("squares can't hold up to {} mines", ms.per_square));
+    }
+    if ms.lives == 0 {
+        return Err("game has no lives".to_string());
+    }
+
+    let mut mines = 0;
+    let mut flags = 0;
+    let mut hits = 0;
+    let mut covered = 0;
+    for row in 0..ms.rows {
+        for col in 0..ms.cols {
+            let square = ms.get(row, col);
+            if square.status.flags() > ms.per_square || square.status == Status::Flagged(0) {
+                return Err(format!("square {}, {} has {} flags", row, col, square.status.flags()));
+            }
+            flags += square.status.flags() as usize;
+            if square.content.mines() > ms.per_square || square.content == Content::Mines(0) {
+                return Err(format!("square {}, {} holds {} mines", row, col, square.content.mines()));
+            }
+            if square.content.is_mine() {
+                mines += square.content.mines() as usize;
+                // mines set off count as flagged
+                if square.status == Status::Uncovered {
+                    flags += square.content.mines() as usize;
+                    hits += 1;
+                }
+                continue;
+            }
+            if square.status != Status::Uncovered {
+                covered += 1;
+            }
+            let around: usize = ms.neighbours(row, col)
+                .map(|(r, c)| ms.get(r, c).content.mines() as usize)
+                .sum();
+            if square.content.number() != Some(around as u8) {
+                return Err(format!("square {}, {} has the wrong number", row, col));
+            }
+        }
+    }
+
+    if ms.generated && mines != ms.mines {
+        return Err(format!("board holds {} mines but should hold {}", mines, ms.mines));
+    }
+    if !ms.generated && (mines != 0 || ms.started || covered != ms.rows * ms.cols) {
+        return Err("board was played before its mines were placed".to_string());
+    }
+    if hits != ms.hits {
+        return Err(format!("{} mines were set off but the count says {}", hits, ms.hits));
+    }
+    // a chord can set off several mines at once, taking more lives than were left
+    if ms.lost != (hits >= ms.lives) {
+        return Err(format!("{} mines were set off with {} lives, but the game is marked {}", hits, ms.lives, if ms.lost { "lost" } else { "not lost" }));
+    }
+    if flags != ms.flags {
+        return Err(format!("{} flags are placed but the count says {}", flags, ms.flags));
+    }
+    // until the mines are placed, every square is covered and counted as clear
+    let left = if ms.generated { covered } else { covered.saturating_sub(ms.mines) };
+    if !ms.lost && left != ms.left {
+        return Err(format!("{} squares are left to clear but the count says {}", left, ms.left));
+    }
+    if ms.won && ms.lost {
+        return Err("game is both won and lost".to_string());
+    }
+    if ms.won && ms.left > 0 {
+        return Err(format!("game is won with {} squares left to clear", ms.left));
+    }
+    if !ms.time.is_finite() || ms.time < 0.0 {
+        return Err("elapsed time is invalid".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::minesweeper::Difficulty;
+
+    /// An intermediate game a few moves in
+    fn game_in_progress() -> MineSweeper {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Intermediate, 11));
+        game.reveal(8, 8);
+        game.toggle_mark(0, 0);
+        game.toggle_mark(0, 1);
+        game.toggle_mark(0, 1);
+        game.tick(12.5);
+        game.minesweeper
+    }
+
+    #[test]
+    fn round_trip_keeps_everything() {
+        let ms = game_in_progress();
+        let loaded = from_str(&to_string(&ms)).unwrap();
+        assert_eq!(loaded.field, ms.field);
+        assert_eq!((loaded.rows, loaded.cols, loaded.mines), (ms.rows, ms.cols, ms.mines));
+        assert_eq!((loaded.left, loaded.flags), (ms.left, ms.flags));
+        assert_eq!((loaded.won, loaded.lost, loaded.started), (ms.won, ms.lost, ms.started));
+        assert_eq!(loaded.time, ms.time);
+        assert_eq!(loaded.seed, ms.seed);
+    }
+
+    #[test]
+    fn round_trip_before_first_reveal() {
+        let ms = MineSweeper::with_difficulty(Difficulty::Beginner, 5);
+        let loaded = from_str(&to_string(&ms)).unwrap();
+        assert!(!loaded.generated);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let saved = to_string(&game_in_progress()).replacen(&format!("\"version\":{}", VERSION), "\"version\":99", 1);
+        assert!(matches!(from_str(&saved), Err(SaveError::Version(Some(99)))));
+    }
+
+    /// `ms` saved as version 1 would have saved it, with at most one mine or flag on a square
+    fn version_1(ms: &MineSweeper) -> String {
+        let mut file: Value = serde_json::from_str(&to_string(ms)).unwrap();
+        file["version"] = json!(1);
+        file["game"].as_object_mut().unwrap().remove("per_square");
+        for square in file["game"]["field"].as_array_mut().unwrap().iter_mut().flat_map(|row| row.as_array_mut().unwrap()) {
+            let content = match square["content"].get("Number").and_then(Value::as_u64) {
+                Some(number) => json!(["Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight"][number as usize]),
+                None => json!("Mine"),
+            };
+            square["content"] = content;
+            if square["status"].get("Flagged").is_some() {
+                square["status"] = json!("Flagged");
+            }
+        }
+        file.to_string()
+    }
+
+    #[test]
+    fn reads_version_1_saves() {
+        let ms = game_in_progress();
+        let saved = version_1(&ms);
+        assert!(saved.contains("\"Flagged\"") && saved.contains("\"Zero\""));
+        let loaded = from_str(&saved).unwrap();
+        assert_eq!(loaded.field, ms.field);
+        assert_eq!((loaded.per_square, loaded.flags), (1, ms.flags));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let saved = to_string(&game_in_progress());
+        assert!(matches!(from_str(&saved[..saved.len() / 2]), Err(SaveError::Format(_))));
+    }
+
+    #[test]
+    fn rejects_wrong_numbers() {
+        let mut ms = game_in_progress();
+        let square = ms.field.iter_mut().flatten()
+            .find(|square| square.content == Content::Number(0))
+            .unwrap();
+        square.content = Content::Number(3);
+        assert!(matches!(from_str(&to_string(&ms)), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_wrong_counts() {
+        let mut ms = game_in_progress();
+        ms.left += 1;
+        assert!(matches!(from_str(&to_string(&ms)), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn round_trip_after_a_chord_sets_off_several_mines() {
+        let mut ms = MineSweeper::new(3, 3, 2, 0);
+        // * . .    with the wrong squares flagged, chording on the 2
+        // . 2 .    sets off both mines with one life to lose
+        // * . .
+        ms.place_mines(&[(0, 0), (2, 0)]);
+        let mut game = Game::new(ms);
+        game.reveal(1, 1);
+        game.toggle_mark(0, 1);
+        game.toggle_mark(0, 2);
+        game.chord(1, 1);
+        assert!(game.minesweeper.lost);
+        assert_eq!(game.minesweeper.hits, 2);
+        assert_eq!(from_str(&to_string(&game.minesweeper)).unwrap(), game.minesweeper);
+
+        let mut ms = game.minesweeper;
+        ms.lost = false;
+        assert!(matches!(from_str(&to_string(&ms)), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_wins_with_squares_left() {
+        let mut ms = game_in_progress();
+        ms.won = true;
+        assert!(matches!(from_str(&to_string(&ms)), Err(SaveError::Corrupt(_))));
+    }
+}
@@ -0,0 +1,742 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::minesweeper::{Content, Field, Status};
+use crate::topology::Topology;
+
+/// A position on the board, `(row, col)`
+pub type Pos = (usize, usize);
+
+/// Squares proven safe or proven to hold a mine
+#[derive(Debug, Default, PartialEq)]
+pub struct Deductions {
+    pub safe: BTreeSet<Pos>,
+    pub mines: BTreeSet<Pos>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// A square the solver can vouch for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hint {
+    Safe(Pos),
+    Mine(Pos),
+}
+
+/// Largest group of linked unknown squares enumerated exhaustively;
+/// bigger ones are left to the cheaper rules
+const MAX_ENUMERATED: usize = 48;
+
+/// Everything a player can know about a board without guessing:
+/// the numbers uncovered so far and the squares proven by them.
+/// Every square is taken to hold at most one mine.
+pub struct Knowledge {
+    pub rows: usize,
+    pub cols: usize,
+    pub topology: Topology,
+    /// The number shown on each uncovered square
+    numbers: Vec<Vec<Option<u8>>>,
+    /// Squares proven to hold a mine
+    mines: Vec<Vec<bool>>,
+    /// Covered squares proven to be clear
+    safe: Vec<Vec<bool>>,
+}
+
+/// The unknown squares around an uncovered number and how many of them are mines
+struct Constraint {
+    unknown: Vec<Pos>,
+    mines: usize,
+}
+
+impl Knowledge {
+    /// Knowledge of a board with nothing uncovered yet
+    pub fn new(rows: usize, cols: usize, topology: Topology) -> Self {
+        Knowledge {
+            rows,
+            cols,
+            topology,
+            numbers: vec![vec![None; cols]; rows],
+            mines: vec![vec![false; cols]; rows],
+            safe: vec![vec![false; cols]; rows],
+        }
+    }
+
+    /// What the player can see of `field`: the numbers uncovered so far,
+    /// and any mine uncovered by losing. Flags are ignored, since they may be wrong.
+    pub fn visible(field: &Field, topology: Topology) -> Self {
+        let mut knowledge = Knowledge::new(field.len(), field[0].len(), topology);
+        for (row, squares) in field.iter().enumerate() {
+            for (col, square) in squares.iter().enumerate() {
+                if square.status != Status::Uncovered {
+                    continue;
+                }
+                match square.content {
+                    Content::Mines(_) => knowledge.mark_mine(row, col),
+                    Content::Number(number) => knowledge.uncover(row, col, number),
+                }
+            }
+        }
+        knowledge
+    }
+
+    pub fn is_uncovered(&self, row: usize, col: usize) -> bool {
+        self.numbers[row][col].is_some()
+    }
+
+    /// Records that `(row, col)` was uncovered and shows `number`
+    pub fn uncover(&mut self, row: usize, col: usize, number: u8) {
+        self.numbers[row][col] = Some(number);
+    }
+
+    /// Records that `(row, col)` was proven to hold a mine
+    pub fn mark_mine(&mut self, row: usize, col: usize) {
+        self.mines[row][col] = true;
+    }
+
+    /// Records that `(row, col)` was proven clear without uncovering it
+    pub fn mark_safe(&mut self, row: usize, col: usize) {
+        self.safe[row][col] = true;
+    }
+
+    /// The constraint an uncovered square puts on its unknown neighbours,
+    /// if it has any unknown neighbours left
+    fn constraint(&self, row: usize, col: usize) -> Option<Constraint> {
+        let number = self.numbers[row][col]? as usize;
+        let mut unknown = vec![];
+        let mut known_mines = 0;
+        for (r, c) in self.topology.neighbours(self.rows, self.cols, row, col) {
+            if self.mines[r][c] {
+                known_mines += 1;
+            } else if self.numbers[r][c].is_none() && !self.safe[r][c] {
+                unknown.push((r, c));
+            }
+        }
+        if unknown.is_empty() {
+            return None;
+        }
+        Some(Constraint { unknown, mines: number.saturating_sub(known_mines) })
+    }
+
+    /// The constraints of every uncovered square with unknown neighbours
+    fn constraints(&self) -> Vec<Constraint> {
+        (0..self.rows)
+            .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+            .filter_map(|(row, col)| self.constraint(row, col))
+            .collect()
+    }
+
+    /// Finds squares that are certainly safe or certainly mines, using the number on
+    /// each square alone and then the overlap between pairs of nearby numbers
+    pub fn deduce(&self) -> Deductions {
+        let mut deductions = Deductions::default();
+        let constraints = self.constraints();
+
+        // single square: all unknowns are mines, or none are
+        for constraint in &constraints {
+            if constraint.mines == 0 {
+                deductions.safe.extend(&constraint.unknown);
+            } else if constraint.mines == constraint.unknown.len() {
+                deductions.mines.extend(&constraint.unknown);
+            }
+        }
+        if !deductions.is_empty() {
+            return deductions;
+        }
+
+        // pairs: when one constraint's unknowns are a subset of another's,
+        // the difference holds exactly the difference in mines
+        for a in &constraints {
+            for b in &constraints {
+                if a.unknown.len() >= b.unknown.len() || !a.unknown.iter().all(|pos| b.unknown.contains(pos)) {
+                    continue;
+                }
+                let rest = b.unknown.iter().filter(|pos| !a.unknown.contains(pos));
+                if b.mines == a.mines {
+                    deductions.safe.extend(rest);
+                } else if b.mines.checked_sub(a.mines) == Some(b.unknown.len() - a.unknown.len()) {
+                    deductions.mines.extend(rest);
+                }
+            }
+        }
+        deductions
+    }
+
+    /// Finds squares that are mines in every arrangement of mines agreeing with the
+    /// numbers, or in none of them, by trying each arrangement around the uncovered
+    /// squares. Catches what `deduce` misses, at a cost that grows quickly with the
+    /// number of unknown squares touching each other through the numbers.
+    pub fn enumerate(&self) -> Deductions {
+        let constraints = self.constraints();
+
+        let mut deductions = Deductions::default();
+        for component in components(&constraints) {
+            if component.unknown.len() > MAX_ENUMERATED {
+                continue;
+            }
+            let outcomes = component.outcomes();
+            for (pos, (mine, safe)) in component.unknown.iter().zip(outcomes) {
+                if !mine {
+                    deductions.safe.insert(*pos);
+                } else if !safe {
+                    deductions.mines.insert(*pos);
+                }
+            }
+        }
+        deductions
+    }
+
+    /// Everything that can be proven, applying the cheap rules for as long as they
+    /// find something and enumerating when they don't. What is proven is recorded,
+    /// so it is taken into account by later calls.
+    pub fn solve(&mut self) -> Deductions {
+        let mut proven = Deductions::default();
+        loop {
+            let mut deductions = self.deduce();
+            if deductions.is_empty() {
+                deductions = self.enumerate();
+            }
+            if deductions.is_empty() {
+                return proven;
+            }
+            for &(row, col) in &deductions.mines {
+                self.mark_mine(row, col);
+            }
+            for &(row, col) in &deductions.safe {
+                self.mark_safe(row, col);
+            }
+            proven.mines.extend(deductions.mines);
+            proven.safe.extend(deductions.safe);
+        }
+    }
+}
+
+/// Unknown squares linked to each other through the constraints on them,
+/// so their mines have to be arranged together
+struct Component {
+    unknown: Vec<Pos>,
+    /// Each constraint on the group, as indices into `unknown` and a mine count
+    constraints: Vec<(Vec<usize>, usize)>,
+}
+
+/// Splits the unknown squares under `constraints` into independent groups
+fn components(constraints: &[Constraint]) -> Vec<Component> {
+    let mut assigned = vec![false; constraints.len()];
+    let mut components = vec![];
+    for start in 0..constraints.len() {
+        if assigned[start] {
+            continue;
+        }
+        assigned[start] = true;
+        let mut members = vec![start];
+        let mut unknown: Vec<Pos> = vec![];
+        let mut i = 0;
+        while i < members.len() {
+            for pos in &constraints[members[i]].unknown {
+                if unknown.contains(pos) {
+                    continue;
+                }
+                unknown.push(*pos);
+                for (other, constraint) in constraints.iter().enumerate() {
+                    if !assigned[other] && constraint.unknown.contains(pos) {
+                        assigned[other] = true;
+                        members.push(other);
+                    }
+                }
+            }
+            i += 1;
+        }
+        let constraints = members.iter()
+            .map(|&member| {
+                let constraint = &constraints[member];
+                let indices = constraint.unknown.iter()
+                    .map(|pos| unknown.iter().position(|u| u == pos).unwrap())
+                    .collect();
+                (indices, constraint.mines)
+            })
+            .collect();
+        components.push(Component { unknown, constraints });
+    }
+    components
+}
+
+/// How many arrangements of a component hold each number of mines,
+/// overall and with a mine on each of its squares
+struct Tally {
+    /// Arrangements by number of mines
+    arrangements: Vec<f64>,
+    /// For each square, arrangements with a mine on it by number of mines
+    hits: Vec<Vec<f64>>,
+}
+
+impl Component {
+    /// For each unknown square, whether some arrangement puts a mine there
+    /// and whether some arrangement leaves it clear
+    fn outcomes(&self) -> Vec<(bool, bool)> {
+        let mut search = Search::new(self, None);
+        search.assign(0);
+        search.outcomes
+    }
+
+    /// Counts every arrangement, scaled so the most common mine count has one
+    /// arrangement, which keeps products of many components in range
+    fn tally(&self) -> Tally {
+        let n = self.unknown.len();
+        let tally = Tally { arrangements: vec![0.0; n + 1], hits: vec![vec![0.0; n + 1]; n] };
+        let mut search = Search::new(self, Some(tally));
+        search.assign(0);
+        let mut tally = search.tally.unwrap();
+        let scale = tally.arrangements.iter().cloned().fold(0.0, f64::max);
+        if scale > 0.0 {
+            tally.arrangements.iter_mut().chain(tally.hits.iter_mut().flatten()).for_each(|count| *count /= scale);
+        }
+        tally
+    }
+}
+
+/// Backtracking over every arrangement of mines in a component
+struct Search<'a> {
+    component: &'a Component,
+    /// The constraints on each unknown square
+    watching: Vec<Vec<usize>>,
+    /// Mines placed so far under each constraint
+    placed: Vec<usize>,
+    /// Squares under each constraint not yet decided
+    open: Vec<usize>,
+    assignment: Vec<bool>,
+    outcomes: Vec<(bool, bool)>,
+    /// Counts of the arrangements found, when every one is wanted
+    tally: Option<Tally>,
+}
+
+impl<'a> Search<'a> {
+    fn new(component: &'a Component, tally: Option<Tally>) -> Self {
+        let n = component.unknown.len();
+        let mut watching = vec![vec![]; n];
+        for (c, (indices, _)) in component.constraints.iter().enumerate() {
+            for &i in indices {
+                watching[i].push(c);
+            }
+        }
+        Search {
+            component,
+            watching,
+            placed: vec![0; component.constraints.len()],
+            open: component.constraints.iter().map(|(indices, _)| indices.len()).collect(),
+            assignment: vec![false; n],
+            outcomes: vec![(false, false); n],
+            tally,
+        }
+    }
+
+    fn assign(&mut self, i: usize) {
+        if i == self.assignment.len() {
+            for (outcome, &mine) in self.outcomes.iter_mut().zip(&self.assignment) {
+                if mine { outcome.0 = true } else { outcome.1 = true }
+            }
+            if let Some(tally) = &mut self.tally {
+                let mines = self.assignment.iter().filter(|&&mine| mine).count();
+                tally.arrangements[mines] += 1.0;
+                for (hits, &mine) in tally.hits.iter_mut().zip(&self.assignment) {
+                    if mine { hits[mines] += 1.0 }
+                }
+            }
+            return;
+        }
+        // once every square has been seen both ways nothing more can be learned,
+        // unless every arrangement is being counted
+        if self.tally.is_none() && self.outcomes.iter().all(|&(mine, safe)| mine && safe) {
+            return;
+        }
+        for &mine in &[false, true] {
+            if self.fits(i, mine) {
+                self.set(i, mine, true);
+                self.assign(i + 1);
+                self.set(i, mine, false);
+            }
+        }
+    }
+
+    /// Whether deciding square `i` leaves every constraint on it satisfiable
+    fn fits(&self, i: usize, mine: bool) -> bool {
+        self.watching[i].iter().all(|&c| {
+            let wanted = self.component.constraints[c].1;
+            let placed = self.placed[c] + mine as usize;
+            placed <= wanted && placed + self.open[c] > wanted
+        })
+    }
+
+    /// Decides square `i`, or undoes the decision
+    fn set(&mut self, i: usize, mine: bool, doing: bool) {
+        self.assignment[i] = mine && doing;
+        for &c in &self.watching[i] {
+            if doing {
+                self.open[c] -= 1;
+                self.placed[c] += mine as usize;
+            } else {
+                self.open[c] += 1;
+                self.placed[c] -= mine as usize;
+            }
+        }
+    }
+}
+
+/// Everything that can be proven from what the player can see of `field`
+pub fn solve(field: &Field, topology: Topology) -> Deductions {
+    Knowledge::visible(field, topology).solve()
+}
+
+/// A square worth pointing out to the player: a covered square that is certainly
+/// clear, or failing that an unflagged square that is certainly a mine
+pub fn hint(field: &Field, topology: Topology) -> Option<Hint> {
+    let deductions = solve(field, topology);
+    let status = |&(row, col): &Pos| &field[row][col].status;
+    deductions.safe.iter()
+        .find(|pos| !matches!(status(pos), Status::Flagged(_)))
+        .map(|&pos| Hint::Safe(pos))
+        .or_else(|| deductions.mines.iter()
+            .find(|pos| !matches!(status(pos), Status::Flagged(_)))
+            .map(|&pos| Hint::Mine(pos)))
+}
+
+/// The chance of a mine on each covered square of `field`, given the numbers uncovered
+/// so far and that the board holds `mines` mines in all. Uncovered squares have none.
+///
+/// Arrangements of mines around the numbers are enumerated, and each is weighted by
+/// the ways of placing the rest of the mines among the squares no number touches.
+/// Returns `None` when the numbers are too tangled to enumerate, or contradict each other.
+pub fn probabilities(field: &Field, topology: Topology, mines: usize) -> Option<Vec<Vec<Option<f64>>>> {
+    let mut knowledge = Knowledge::visible(field, topology);
+    knowledge.solve();
+    let (rows, cols) = (knowledge.rows, knowledge.cols);
+
+    let components = components(&knowledge.constraints());
+    if components.iter().any(|component| component.unknown.len() > MAX_ENUMERATED) {
+        return None;
+    }
+    let tallies: Vec<Tally> = components.iter().map(Component::tally).collect();
+
+    let mut frontier = vec![vec![false; cols]; rows];
+    for &(row, col) in components.iter().flat_map(|component| &component.unknown) {
+        frontier[row][col] = true;
+    }
+    let squares = || (0..rows).flat_map(move |row| (0..cols).map(move |col| (row, col)));
+    let known_mines = squares().filter(|&(row, col)| knowledge.mines[row][col]).count();
+    let interior = squares()
+        .filter(|&(row, col)| {
+            !knowledge.mines[row][col] && !knowledge.is_uncovered(row, col)
+                && !knowledge.safe[row][col] && !frontier[row][col]
+        })
+        .count();
+    let remaining = mines.checked_sub(known_mines)?;
+
+    // relative ways of putting `r` mines among the interior squares,
+    // scaled by the most ways any feasible count has so they stay in range
+    let ln_factorial: Vec<f64> = (0..=interior)
+        .scan(0.0, |sum, n| {
+            if n > 0 { *sum += (n as f64).ln() }
+            Some(*sum)
+        })
+        .collect();
+    let ln_choose = |r: usize| ln_factorial[interior] - ln_factorial[r] - ln_factorial[interior - r];
+    let most_frontier: usize = components.iter().map(|component| component.unknown.len()).sum();
+    let feasible = remaining.saturating_sub(most_frontier)..=remaining.min(interior);
+    let peak = feasible.map(ln_choose).fold(f64::NEG_INFINITY, f64::max);
+    let ways = |r: isize| {
+        if r < 0 || r as usize > interior { 0.0 } else { (ln_choose(r as usize) - peak).exp() }
+    };
+
+    // weight of the interior once `k` mines are in the frontier components other than `skip`
+    let rest = |skip: Option<usize>| {
+        let others = tallies.iter().enumerate()
+            .filter(|&(j, _)| Some(j) != skip)
+            .fold(vec![1.0], |total, (_, tally)| convolve(&total, &tally.arrangements));
+        move |k: usize| -> f64 {
+            others.iter().enumerate()
+                .map(|(m, count)| count * ways(remaining as isize - (k + m) as isize))
+                .sum()
+        }
+    };
+
+    let everything = rest(None);
+    let total = everything(0);
+    if total <= 0.0 || !total.is_finite() {
+        return None;
+    }
+    let interior_odds = if interior == 0 {
+        0.0
+    } else {
+        let all = tallies.iter().fold(vec![1.0], |total, tally| convolve(&total, &tally.arrangements));
+        all.iter().enumerate()
+            .map(|(m, count)| {
+                let r = remaining as isize - m as isize;
+                count * ways(r) * r.max(0) as f64 / interior as f64
+            })
+            .sum::<f64>() / total
+    };
+
+    let mut odds: Vec<Vec<Option<f64>>> = (0..rows)
+        .map(|row| (0..cols)
+            .map(|col| {
+                if knowledge.mines[row][col] {
+                    Some(1.0)
+                } else if knowledge.is_uncovered(row, col) {
+                    None
+                } else if knowledge.safe[row][col] {
+                    Some(0.0)
+                } else {
+                    Some(interior_odds)
+                }
+            })
+            .collect())
+        .collect();
+    for (j, (component, tally)) in components.iter().zip(&tallies).enumerate() {
+        let weight = rest(Some(j));
+        let weights: Vec<f64> = (0..tally.arrangements.len()).map(weight).collect();
+        for (&(row, col), hits) in component.unknown.iter().zip(&tally.hits) {
+            let p = hits.iter().zip(&weights).map(|(hits, weight)| hits * weight).sum::<f64>() / total;
+            odds[row][col] = Some(p);
+        }
+    }
+    Some(odds)
+}
+
+/// Counts of two independent things added together, by total
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut sum = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            sum[i + j] += x * y;
+        }
+    }
+    sum
+}
+
+/// Plays a board without guessing, starting by revealing `(row, col)`,
+/// and returns how many squares without a mine were left covered when
+/// deduction ran out. Zero means the board can be cleared by logic alone.
+pub fn unsolved(field: &Field, topology: Topology, row: usize, col: usize) -> usize {
+    let rows = field.len();
+    let cols = field[0].len();
+    let safe_squares = field.iter().flatten().filter(|square| !square.content.is_mine()).count();
+    if field[row][col].content.is_mine() {
+        return safe_squares;
+    }
+
+    let mut knowledge = Knowledge::new(rows, cols, topology);
+    let mut uncovered = flood(field, &mut knowledge, row, col);
+    loop {
+        let deductions = knowledge.deduce();
+        if deductions.is_empty() {
+            break;
+        }
+        for (r, c) in deductions.mines {
+            knowledge.mark_mine(r, c);
+        }
+        for (r, c) in deductions.safe {
+            uncovered += flood(field, &mut knowledge, r, c);
+        }
+    }
+    safe_squares - uncovered
+}
+
+/// Uncovers `(row, col)` in `knowledge` from the true `field`, opening up
+/// around zeros, and returns how many squares were newly uncovered
+fn flood(field: &Field, knowledge: &mut Knowledge, row: usize, col: usize) -> usize {
+    let mut count = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back((row, col));
+    while let Some((r, c)) = queue.pop_front() {
+        if knowledge.is_uncovered(r, c) {
+            continue;
+        }
+        let number = field[r][c].content.number().unwrap_or(0);
+        knowledge.uncover(r, c, number);
+        count += 1;
+        if number == 0 {
+            queue.extend(knowledge.topology.neighbours(knowledge.rows, knowledge.cols, r, c));
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, Outcome};
+    use crate::minesweeper::{Difficulty, Generation, MineSweeper, SafeStart};
+
+    #[test]
+    fn single_number_proves_mine() {
+        // 1 1
+        // 1 ?  <- the only square the ones can be pointing at
+        let mut knowledge = Knowledge::new(2, 2, Topology::Flat);
+        knowledge.uncover(0, 0, 1);
+        knowledge.uncover(0, 1, 1);
+        knowledge.uncover(1, 0, 1);
+        let deductions = knowledge.deduce();
+        assert_eq!(deductions.mines.into_iter().collect::<Vec<_>>(), vec![(1, 1)]);
+        assert!(deductions.safe.is_empty());
+    }
+
+    #[test]
+    fn pair_of_numbers_proves_safe() {
+        // ? ? ?
+        // 1 1 ?  <- the left 1's mine is also the middle 1's, freeing the right column
+        let mut knowledge = Knowledge::new(2, 3, Topology::Flat);
+        knowledge.uncover(1, 0, 1);
+        knowledge.uncover(1, 1, 1);
+        let deductions = knowledge.deduce();
+        assert_eq!(deductions.safe.into_iter().collect::<Vec<_>>(), vec![(0, 2), (1, 2)]);
+        assert!(deductions.mines.is_empty());
+    }
+
+    #[test]
+    fn fifty_fifty_proves_nothing() {
+        // ? ?
+        // 1 1
+        let mut knowledge = Knowledge::new(2, 2, Topology::Flat);
+        knowledge.uncover(1, 0, 1);
+        knowledge.uncover(1, 1, 1);
+        assert!(knowledge.deduce().is_empty());
+    }
+
+    #[test]
+    fn pairs_with_more_mines_in_the_subset_prove_nothing() {
+        // ? ? ?
+        // 3 1 1  <- every square around the 3 touches the middle 1, so no real board looks like this
+        // ? ? ?
+        let mut knowledge = Knowledge::new(3, 3, Topology::Flat);
+        knowledge.uncover(1, 0, 3);
+        knowledge.uncover(1, 1, 1);
+        knowledge.uncover(1, 2, 1);
+        let deductions = knowledge.deduce();
+        assert!(deductions.mines.is_empty());
+    }
+
+    #[test]
+    fn enumeration_proves_what_pairs_cannot() {
+        // 0 . 0 .
+        // . 1 2 .
+        // ? ? ? ?  <- the 1 and 2 share two squares, so the 2's third is a mine
+        // 1 ? ? ?     and the 1's third is clear; neither set contains the other
+        let mut knowledge = Knowledge::new(4, 4, Topology::Flat);
+        knowledge.uncover(0, 0, 0);
+        knowledge.uncover(0, 2, 0);
+        knowledge.uncover(1, 1, 1);
+        knowledge.uncover(1, 2, 2);
+        knowledge.uncover(3, 0, 1);
+        for &(row, col) in &[(0, 1), (0, 3), (1, 0), (1, 3)] {
+            knowledge.mark_safe(row, col);
+        }
+        assert!(knowledge.deduce().is_empty());
+        let deductions = knowledge.enumerate();
+        assert_eq!(deductions.safe.into_iter().collect::<Vec<_>>(), vec![(2, 0)]);
+        assert_eq!(deductions.mines.into_iter().collect::<Vec<_>>(), vec![(2, 3)]);
+    }
+
+    #[test]
+    fn solve_from_visible_state_is_sound() {
+        for seed in 0..20 {
+            let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Expert, seed));
+            game.reveal(8, 15);
+            let deductions = solve(&game.minesweeper.field, Topology::Flat);
+            assert!(!deductions.is_empty());
+            for (row, col) in deductions.safe {
+                assert!(!game.minesweeper.get(row, col).content.is_mine());
+                assert_ne!(game.minesweeper.get(row, col).status, Status::Uncovered);
+            }
+            for (row, col) in deductions.mines {
+                assert!(game.minesweeper.get(row, col).content.is_mine());
+            }
+        }
+    }
+
+    #[test]
+    fn hint_prefers_safe_squares() {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Intermediate, 4));
+        assert_eq!(hint(&game.minesweeper.field, Topology::Flat), None);
+        game.reveal(8, 8);
+        match hint(&game.minesweeper.field, Topology::Flat) {
+            Some(Hint::Safe((row, col))) => {
+                game.reveal(row, col);
+                assert_eq!(game.state(), Outcome::Playing);
+            },
+            other => panic!("expected a safe square, got {:?}", other),
+        }
+    }
+
+    /// Exact odds found by trying every placement of the mines among the covered squares
+    fn brute_force_odds(ms: &MineSweeper) -> Vec<Vec<f64>> {
+        fn place(ms: &MineSweeper, covered: &[Pos], left: usize, chosen: &mut Vec<Pos>, hits: &mut Vec<Vec<f64>>, total: &mut f64) {
+            if left == 0 {
+                let consistent = (0..ms.rows).flat_map(|row| (0..ms.cols).map(move |col| (row, col)))
+                    .filter(|&(row, col)| ms.get(row, col).status == Status::Uncovered)
+                    .all(|(row, col)| {
+                        let around = ms.neighbours(row, col).filter(|pos| chosen.contains(pos)).count();
+                        ms.get(row, col).content == Content::Number(around as u8)
+                    });
+                if consistent {
+                    *total += 1.0;
+                    for &(row, col) in chosen.iter() {
+                        hits[row][col] += 1.0;
+                    }
+                }
+                return;
+            }
+            for (i, &pos) in covered.iter().enumerate() {
+                chosen.push(pos);
+                place(ms, &covered[i + 1..], left - 1, chosen, hits, total);
+                chosen.pop();
+            }
+        }
+        let covered: Vec<Pos> = (0..ms.rows).flat_map(|row| (0..ms.cols).map(move |col| (row, col)))
+            .filter(|&(row, col)| ms.get(row, col).status != Status::Uncovered)
+            .collect();
+        let mut hits = vec![vec![0.0; ms.cols]; ms.rows];
+        let mut total = 0.0;
+        place(ms, &covered, ms.mines, &mut vec![], &mut hits, &mut total);
+        hits.iter().map(|row| row.iter().map(|hits| hits / total).collect()).collect()
+    }
+
+    #[test]
+    fn probabilities_match_brute_force() {
+        for seed in 0..10 {
+            let mut ms = MineSweeper::new(4, 5, 5, seed);
+            ms.safe_start = SafeStart::Cell;
+            let mut game = Game::new(ms);
+            game.reveal(1, 2);
+            if game.state() != Outcome::Playing {
+                continue;
+            }
+            let odds = probabilities(&game.minesweeper.field, Topology::Flat, 5).unwrap();
+            let expected = brute_force_odds(&game.minesweeper);
+            for row in 0..4 {
+                for col in 0..5 {
+                    match odds[row][col] {
+                        Some(p) => assert!((p - expected[row][col]).abs() < 1e-9, "seed {} square {}, {}", seed, row, col),
+                        None => assert_eq!(game.minesweeper.get(row, col).status, Status::Uncovered),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn probabilities_before_first_reveal_are_even() {
+        let ms = MineSweeper::with_difficulty(Difficulty::Beginner, 1);
+        let odds = probabilities(&ms.field, ms.topology, ms.mines).unwrap();
+        assert!(odds.iter().flatten().all(|&p| (p.unwrap() - 10.0 / 81.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn no_guess_expert_board_clears_by_logic() {
+        let mut ms = MineSweeper::with_difficulty(Difficulty::Expert, 7);
+        ms.safe_start = SafeStart::Opening;
+        ms.generation = Generation::no_guess_attempts(2000);
+        ms.generate(8, 15);
+        assert_eq!(unsolved(&ms.field, ms.topology, 8, 15), 0);
+    }
+}
@@ -0,0 +1,295 @@
+//! A record of every finished game, kept between sessions.
+//!
+//! Only the games themselves are stored; best times, win streaks and the spread
+//! of times are all worked out from them when asked for. The record lives in
+//! `minesweeper/stats.json` under the platform's data directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Score;
+use crate::minesweeper::{Difficulty, MineSweeper};
+use crate::save::SaveError;
+use crate::topology::Topology;
+
+/// How many best times are kept on show for each difficulty
+pub const BEST_TIMES: usize = 10;
+
+/// A finished game
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Finished {
+    pub name: String,
+    pub date: NaiveDate,
+    /// The difficulty's name, as it is given on the command line
+    pub difficulty: String,
+    pub won: bool,
+    /// Seconds played
+    pub time: f64,
+    pub bbbv: usize,
+    pub clicks: usize,
+    /// Moves taken back during the game
+    #[serde(default)]
+    pub undos: usize,
+    /// Mines set off during the game, the last one losing it unless lives were left
+    #[serde(default)]
+    pub hits: usize,
+    /// Games on boards with different topologies are kept apart
+    #[serde(default)]
+    pub topology: Topology,
+    /// As are games where squares could hold several mines
+    #[serde(default = "per_square_default")]
+    pub per_square: u8,
+}
+
+/// Games recorded before mines could share a square had one to a square
+fn per_square_default() -> u8 {
+    1
+}
+
+impl Finished {
+    /// Records how `ms`, which must be over, was played
+    pub fn new(ms: &MineSweeper, name: &str, date: NaiveDate) -> Self {
+        let score = Score::of(ms);
+        Finished {
+            name: name.to_string(),
+            date,
+            difficulty: Difficulty::of(ms.rows, ms.cols, ms.mines).to_string(),
+            won: ms.won,
+            time: score.time,
+            bbbv: score.bbbv,
+            clicks: score.clicks,
+            undos: ms.undos,
+            hits: ms.hits,
+            topology: ms.topology,
+            per_square: ms.per_square,
+        }
+    }
+
+    /// Whether the game was won without taking any moves back or setting off a mine
+    pub fn real_win(&self) -> bool {
+        self.won && self.undos == 0 && self.hits == 0
+    }
+
+    pub fn score(&self) -> Score {
+        Score { bbbv: self.bbbv, time: self.time, clicks: self.clicks }
+    }
+}
+
+/// Totals for one difficulty, where games won with the help of undo or spare lives
+/// count as played but not won
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary<'a> {
+    pub played: usize,
+    pub won: usize,
+    /// Wins in a row up to the last game
+    pub streak: usize,
+    pub longest_streak: usize,
+    /// Mines set off in all
+    pub hits: usize,
+    /// The fastest wins, fastest first
+    pub best: Vec<&'a Finished>,
+}
+
+impl Summary<'_> {
+    /// The share of games won, from zero to one
+    pub fn win_rate(&self) -> f64 {
+        if self.played > 0 { self.won as f64 / self.played as f64 } else { 0.0 }
+    }
+}
+
+/// Every game finished so far, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(default)]
+    pub games: Vec<Finished>,
+}
+
+impl Stats {
+    /// Where the statistics are kept, if the platform has a data directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("minesweeper").join("stats.json"))
+    }
+
+    /// Reads the statistics at `path`, starting afresh if there are none yet
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        match fs::read_to_string(path) {
+            Ok(s) => Ok(serde_json::from_str(&s)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Stats::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the statistics to `path`, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self).expect("statistics always serialize"))?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, game: Finished) {
+        self.games.push(game);
+    }
+
+    /// Games played at `difficulty` on boards joined up by `topology`
+    /// with up to `per_square` mines on a square, oldest first
+    fn at(&self, difficulty: Difficulty, topology: Topology, per_square: u8) -> impl Iterator<Item = &Finished> {
+        let name = difficulty.to_string();
+        self.games.iter()
+            .filter(move |game| game.difficulty == name && game.topology == topology && game.per_square == per_square)
+    }
+
+    pub fn summary(&self, difficulty: Difficulty, topology: Topology, per_square: u8) -> Summary<'_> {
+        let mut summary = Summary::default();
+        for game in self.at(difficulty, topology, per_square) {
+            summary.played += 1;
+            summary.hits += game.hits;
+            if game.real_win() {
+                summary.won += 1;
+                summary.streak += 1;
+                summary.longest_streak = summary.longest_streak.max(summary.streak);
+                summary.best.push(game);
+            } else {
+                summary.streak = 0;
+            }
+        }
+        // a stable sort keeps the earlier of two equal times first
+        summary.best.sort_by(|a, b| a.time.total_cmp(&b.time));
+        summary.best.truncate(BEST_TIMES);
+        summary
+    }
+
+    /// Counts the winning times at `difficulty` falling in each of `buckets` equal
+    /// spans from the fastest to the slowest, returning each span's start and count
+    pub fn distribution(&self, difficulty: Difficulty, topology: Topology, per_square: u8, buckets: usize) -> Vec<(f64, usize)> {
+        let times: Vec<f64> = self.at(difficulty, topology, per_square).filter(|game| game.real_win()).map(|game| game.time).collect();
+        if times.is_empty() || buckets == 0 {
+            return vec![];
+        }
+        let fastest = times.iter().copied().fold(f64::INFINITY, f64::min);
+        let slowest = times.iter().copied().fold(0.0, f64::max);
+        let width = ((slowest - fastest) / buckets as f64).max(f64::EPSILON);
+        let mut counts = vec![0; buckets];
+        for time in times {
+            counts[(((time - fastest) / width) as usize).min(buckets - 1)] += 1;
+        }
+        counts.into_iter().enumerate().map(|(i, count)| (fastest + i as f64 * width, count)).collect()
+    }
+
+    /// Every game as comma separated values, one row each under a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,name,difficulty,won,time,3bv,clicks,3bv_per_second,efficiency,undos,hits,topology,mines_per_square\n");
+        for game in &self.games {
+            let score = game.score();
+            csv += &format!(
+                "{},{},{},{},{:.3},{},{},{:.3},{:.3},{},{},{},{}\n",
+                game.date,
+                csv_field(&game.name),
+                game.difficulty,
+                game.won,
+                game.time,
+                game.bbbv,
+                game.clicks,
+                score.bbbv_per_second(),
+                score.efficiency(),
+                game.undos,
+                game.hits,
+                game.topology,
+                game.per_square,
+            );
+        }
+        csv
+    }
+
+    /// Writes every game to `path` as comma separated values
+    pub fn export(&self, path: &Path) -> Result<(), SaveError> {
+        fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+}
+
+/// Quotes a field if it holds anything that would break up the row
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: Difficulty, won: bool, time: f64) -> Finished {
+        Finished {
+            name: "player".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            difficulty: difficulty.to_string(),
+            won,
+            time,
+            bbbv: 10,
+            clicks: 12,
+            undos: 0,
+            hits: 0,
+            topology: Topology::Flat,
+            per_square: 1,
+        }
+    }
+
+    #[test]
+    fn summarises_each_difficulty() {
+        let mut stats = Stats::default();
+        for &(won, time) in &[(true, 30.0), (false, 5.0), (true, 20.0), (true, 25.0), (true, 40.0), (false, 2.0)] {
+            stats.record(game(Difficulty::Beginner, won, time));
+        }
+        stats.record(game(Difficulty::Expert, true, 10.0));
+        let mut assisted = game(Difficulty::Beginner, true, 1.0);
+        assisted.undos = 2;
+        stats.record(assisted);
+        let mut survived = game(Difficulty::Beginner, true, 1.0);
+        survived.hits = 1;
+        stats.record(survived);
+
+        let summary = stats.summary(Difficulty::Beginner, Topology::Flat, 1);
+        assert_eq!((summary.played, summary.won, summary.streak, summary.longest_streak), (8, 4, 0, 3));
+        assert_eq!(summary.hits, 1);
+        let best: Vec<f64> = summary.best.iter().map(|game| game.time).collect();
+        assert_eq!(best, vec![20.0, 25.0, 30.0, 40.0]);
+        assert_eq!(stats.summary(Difficulty::Intermediate, Topology::Flat, 1), Summary::default());
+        assert_eq!(stats.summary(Difficulty::Beginner, Topology::Flat, 2), Summary::default());
+    }
+
+    #[test]
+    fn distribution_spans_fastest_to_slowest() {
+        let mut stats = Stats::default();
+        for &time in &[10.0, 11.0, 19.0, 30.0] {
+            stats.record(game(Difficulty::Beginner, true, time));
+        }
+        stats.record(game(Difficulty::Beginner, false, 100.0));
+        assert_eq!(stats.distribution(Difficulty::Beginner, Topology::Flat, 1, 2), vec![(10.0, 3), (20.0, 1)]);
+        assert!(stats.distribution(Difficulty::Expert, Topology::Flat, 1, 2).is_empty());
+    }
+
+    #[test]
+    fn round_trips_and_exports() {
+        let path = std::env::temp_dir().join(format!("minesweeper-stats-{}", std::process::id())).join("stats.json");
+        assert_eq!(Stats::load(&path).unwrap(), Stats::default());
+
+        let mut stats = Stats::default();
+        let mut named = game(Difficulty::Custom { rows: 5, cols: 5, mines: 3 }, true, 1.5);
+        named.name = "Smith, \"Jo\"".to_string();
+        stats.record(named);
+        stats.save(&path).unwrap();
+        assert_eq!(Stats::load(&path).unwrap(), stats);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let csv = stats.to_csv();
+        assert_eq!(csv.lines().nth(1), Some("2024-01-01,\"Smith, \"\"Jo\"\"\",5x5x3,true,1.500,10,12,6.667,0.833,0,0,flat,1"));
+    }
+}
@@ -0,0 +1,96 @@
+//! Clearing as many boards as possible before a countdown runs out.
+//!
+//! The countdown runs whether or not a board has been started. As soon as a
+//! board is won or lost it is scored and the next one starts, generated from
+//! the following seed, so a run started from the same seed meets the same boards.
+
+use crate::game::{Game, Outcome};
+use crate::minesweeper::MAX_DISPLAY;
+
+/// A run against the clock
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeAttack {
+    /// Seconds the run lasts
+    pub length: f64,
+    /// Seconds left
+    pub remaining: f64,
+    /// Boards won so far, the score
+    pub cleared: usize,
+    /// Boards lost so far
+    pub lost: usize,
+}
+
+impl TimeAttack {
+    /// A run lasting `length` seconds
+    pub fn new(length: f64) -> Self {
+        TimeAttack { length, remaining: length, cleared: 0, lost: 0 }
+    }
+
+    /// Whether the time has run out
+    pub fn over(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    /// Whole seconds left, rounded up and capped to what the timer display can show
+    pub fn seconds_left(&self) -> u32 {
+        (self.remaining.ceil() as u32).min(MAX_DISPLAY)
+    }
+
+    /// Counts `dt` seconds down, and scores the board in `game` if it has ended,
+    /// starting over on the next one
+    pub fn advance(&mut self, game: &mut Game, dt: f64) {
+        if self.over() { return }
+        self.remaining = (self.remaining - dt).max(0.0);
+        match game.state() {
+            Outcome::Playing => return,
+            Outcome::Won => self.cleared += 1,
+            Outcome::Lost => self.lost += 1,
+        }
+        game.reset(game.minesweeper.seed.wrapping_add(1));
+    }
+
+    /// Starts the run again with the full time and no score
+    pub fn restart(&mut self) {
+        *self = TimeAttack::new(self.length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{Difficulty, MineSweeper};
+
+    #[test]
+    fn scores_each_board_and_moves_on_until_time_runs_out() {
+        let mut game = Game::new(MineSweeper::with_difficulty(Difficulty::Beginner, 7));
+        let mut run = TimeAttack::new(60.0);
+        run.advance(&mut game, 0.5);
+        assert_eq!((run.seconds_left(), run.cleared, game.minesweeper.seed), (60, 0, 7));
+
+        game.reveal(4, 4);
+        for (row, col) in game.minesweeper.layout() {
+            game.toggle_mark(row, col);
+        }
+        for row in 0..9 {
+            for col in 0..9 {
+                game.reveal(row, col);
+            }
+        }
+        assert_eq!(game.state(), Outcome::Won);
+        run.advance(&mut game, 0.5);
+        assert_eq!((run.cleared, run.lost, game.minesweeper.seed), (1, 0, 8));
+        assert!(!game.minesweeper.generated);
+
+        game.reveal(4, 4);
+        let (row, col) = game.minesweeper.layout()[0];
+        game.reveal(row, col);
+        run.advance(&mut game, 0.5);
+        assert_eq!((run.cleared, run.lost, game.minesweeper.seed), (1, 1, 9));
+
+        run.advance(&mut game, 100.0);
+        assert!(run.over());
+        assert_eq!(run.seconds_left(), 0);
+        run.restart();
+        assert_eq!(run, TimeAttack::new(60.0));
+    }
+}
@@ -0,0 +1,185 @@
+//! Which squares touch which.
+//!
+//! Every rule that looks at a square's surroundings (counting mines, opening up
+//! zeros, chording, solving) asks the board's topology for its neighbours, so a
+//! new shape of board only has to be described here.
+//!
+//! Hex boards keep the `(row, col)` positions of square ones, with every odd row
+//! shifted half a cell to the right. Their neighbours are found by stepping in
+//! axial coordinates, where the six directions are the same from every cell.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::solver::Pos;
+
+/// Most neighbours a square can have in any topology
+const MAX_NEIGHBOURS: usize = 8;
+
+/// How the squares of a board are joined up
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Topology {
+    /// A rectangle with edges, as in the original game
+    #[default]
+    Flat,
+    /// The edges wrap around to the opposite side, so every square has eight neighbours
+    Torus,
+    /// Hexagonal cells with six neighbours each, odd rows shifted half a cell to the right
+    Hex,
+}
+
+/// A hex cell in axial coordinates `(q, r)`: `r` is the row, and `q` runs along it
+/// leaning with the rows, so a step to a neighbour is the same anywhere on the board
+pub type Axial = (isize, isize);
+
+/// The steps to each of a hex cell's neighbours, row by row starting with the row above
+const HEX_STEPS: [Axial; 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+
+/// The axial coordinates of the hex cell at `(row, col)`
+pub fn to_axial((row, col): Pos) -> Axial {
+    let r = row as isize;
+    (col as isize - (r - (r & 1)) / 2, r)
+}
+
+/// The position of the hex cell at axial coordinates `(q, r)`, if it isn't off the top or left
+pub fn from_axial((q, r): Axial) -> Option<Pos> {
+    let col = q + (r - (r & 1)) / 2;
+    if r < 0 || col < 0 {
+        return None;
+    }
+    Some((r as usize, col as usize))
+}
+
+/// Parses `flat`, `torus` or `hex`
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Topology::Flat),
+            "torus" => Ok(Topology::Torus),
+            "hex" => Ok(Topology::Hex),
+            _ => Err(format!("unknown topology `{}`", s)),
+        }
+    }
+}
+
+/// Writes the name of the topology in the form it is parsed from
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Topology::Flat => write!(f, "flat"),
+            Topology::Torus => write!(f, "torus"),
+            Topology::Hex => write!(f, "hex"),
+        }
+    }
+}
+
+impl Topology {
+    /// Positions of all squares touching `(row, col)` on a `rows` by `cols` board,
+    /// row by row starting with the row above, each given once
+    pub fn neighbours(self, rows: usize, cols: usize, row: usize, col: usize) -> Neighbours {
+        let mut around = Neighbours { squares: [(0, 0); MAX_NEIGHBOURS], len: 0, next: 0 };
+        if self == Topology::Hex {
+            let (q, r) = to_axial((row, col));
+            for &(dq, dr) in &HEX_STEPS {
+                if let Some(pos) = from_axial((q + dq, r + dr)).filter(|&(nr, nc)| nr < rows && nc < cols) {
+                    around.push(pos);
+                }
+            }
+            return around;
+        }
+        for dr in -1..=1 {
+            for dc in -1..=1 {
+                let pos = if self == Topology::Torus {
+                    Some((wrap(row, dr, rows), wrap(col, dc, cols)))
+                } else {
+                    offset(row, dr, rows).zip(offset(col, dc, cols))
+                };
+                // on a torus only a square or two across, several offsets land on the same square
+                match pos {
+                    Some(pos) if pos != (row, col) && !around.squares[..around.len].contains(&pos) => around.push(pos),
+                    _ => {},
+                }
+            }
+        }
+        around
+    }
+}
+
+/// `i` moved by `delta`, if that stays within `0..len`
+fn offset(i: usize, delta: isize, len: usize) -> Option<usize> {
+    i.checked_add_signed(delta).filter(|&moved| moved < len)
+}
+
+/// `i` moved by `delta`, wrapping around within `0..len`
+fn wrap(i: usize, delta: isize, len: usize) -> usize {
+    (i + len).wrapping_add_signed(delta) % len
+}
+
+/// The squares touching a square, without allocating
+#[derive(Clone, Debug)]
+pub struct Neighbours {
+    squares: [Pos; MAX_NEIGHBOURS],
+    len: usize,
+    next: usize,
+}
+
+impl Neighbours {
+    fn push(&mut self, pos: Pos) {
+        self.squares[self.len] = pos;
+        self.len += 1;
+    }
+}
+
+impl Iterator for Neighbours {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        if self.next == self.len {
+            return None;
+        }
+        self.next += 1;
+        Some(self.squares[self.next - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_boards_stop_at_the_edges() {
+        assert_eq!(Topology::Flat.neighbours(9, 9, 0, 0).collect::<Vec<_>>(), vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(Topology::Flat.neighbours(9, 9, 4, 4).count(), 8);
+        assert_eq!(Topology::Flat.neighbours(1, 1, 0, 0).count(), 0);
+    }
+
+    #[test]
+    fn torus_boards_wrap_around() {
+        let around: Vec<_> = Topology::Torus.neighbours(9, 9, 0, 0).collect();
+        assert_eq!(around, vec![(8, 8), (8, 0), (8, 1), (0, 8), (0, 1), (1, 8), (1, 0), (1, 1)]);
+        // two rows across, the rows above and below are the same row
+        assert_eq!(Topology::Torus.neighbours(2, 9, 0, 4).count(), 5);
+        assert_eq!(Topology::Torus.neighbours(1, 1, 0, 0).count(), 0);
+    }
+
+    #[test]
+    fn hex_cells_touch_six_others() {
+        // an even row leans left of the odd rows around it, and an odd row right
+        assert_eq!(Topology::Hex.neighbours(9, 9, 2, 4).collect::<Vec<_>>(), vec![(1, 3), (1, 4), (2, 3), (2, 5), (3, 3), (3, 4)]);
+        assert_eq!(Topology::Hex.neighbours(9, 9, 3, 4).collect::<Vec<_>>(), vec![(2, 4), (2, 5), (3, 3), (3, 5), (4, 4), (4, 5)]);
+        assert_eq!(Topology::Hex.neighbours(9, 9, 0, 0).count(), 2);
+        for row in 0..9 {
+            for col in 0..9 {
+                let pos = (row, col);
+                assert_eq!(from_axial(to_axial(pos)), Some(pos));
+                for (r, c) in Topology::Hex.neighbours(9, 9, row, col) {
+                    assert!(Topology::Hex.neighbours(9, 9, r, c).any(|around| around == pos));
+                }
+            }
+        }
+    }
+}